@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+use crate::mpp::Mpp;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveOptions {
+    pub n_points: usize,    // uniform samples between v_reverse (or 0) and Voc
+    pub max_points: usize,  // limit including the refinement points
+    pub refine_tol: f64,    // [-] max deviation from linear interpolation, relative to Isc
+    pub v_reverse: f64,     // [V] lowest voltage of the sweep; negative values sample the reverse bias region
+}
+
+impl Default for CurveOptions {
+    fn default() -> Self {
+        CurveOptions { n_points: 50, max_points: 500, refine_tol: 2e-3, v_reverse: 0.0 }
+    }
+}
+
+impl CurveOptions {
+    pub fn with_reverse_bias(mut self, v_reverse: f64) -> Self { self.v_reverse = v_reverse; return self; }
+}
+
+/// Sampled I-V characteristic, ordered by increasing voltage, and its key points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IvCurve {
+    pub v: Vec<f64>,    // [V]
+    pub i: Vec<f64>,    // [A]
+    pub isc: f64,       // [A]
+    pub voc: f64,       // [V]
+    pub mpp: Mpp,
+}
+
+impl IvCurve {
+    pub fn len(&self) -> usize {
+        return self.v.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.v.is_empty();
+    }
+
+    /// P-V curve [W], on the same voltages as `v`.
+    pub fn p(&self) -> Vec<f64> {
+        return self.v.iter().zip(self.i.iter()).map(|(v, i)| v * i).collect();
+    }
+
+    pub fn vmp(&self) -> f64 { return self.mpp.v; }
+    pub fn imp(&self) -> f64 { return self.mpp.i; }
+    pub fn pmp(&self) -> f64 { return self.mpp.p; }
+
+    pub fn fill_factor(&self) -> f64 {
+        return self.mpp.p / (self.isc * self.voc);
+    }
+}
+
+/// Samples `i_from_v` uniformly and bisects every interval whose midpoint deviates from the
+/// linear interpolation by more than `refine_tol * Isc` (knees, bypass activation).
+pub(crate) fn build_curve<F, E>(mut i_from_v: F, isc: f64, voc: f64, mpp: Mpp, opts: &CurveOptions) -> Result<IvCurve, E>
+where F: FnMut(f64) -> Result<f64, E> {
+    let v_start: f64 = opts.v_reverse.min(0.0);
+    let v_end: f64 = voc.max(0.0);
+    let n: usize = opts.n_points.max(2);
+
+    let mut points: Vec<(f64, f64)> = Vec::with_capacity(opts.max_points.max(n + 3));
+    for k in 0..n {
+        let v: f64 = v_start + (v_end - v_start) * (k as f64) / ((n - 1) as f64);
+        let i: f64 = if v == 0.0 { isc } else if k == n - 1 { 0.0 } else { i_from_v(v)? };
+        points.push((v, i));
+    }
+    if v_start < 0.0 {
+        points.push((0.0, isc));
+    }
+    points.push((mpp.v, mpp.i));
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    points.dedup_by(|a, b| a.0 == b.0);
+
+    let tol: f64 = opts.refine_tol * isc.abs().max(f64::MIN_POSITIVE);
+    let min_dv: f64 = 1e-6 * (v_end - v_start).max(f64::MIN_POSITIVE);
+    let mut pending: VecDeque<((f64, f64), (f64, f64))> = points.windows(2).map(|w| (w[0], w[1])).collect();
+    while let Some(((va, ia), (vb, ib))) = pending.pop_front() {
+        if points.len() >= opts.max_points {
+            break;
+        }
+        if vb - va < min_dv {
+            continue;
+        }
+        let vm: f64 = 0.5 * (va + vb);
+        let im: f64 = i_from_v(vm)?;
+        if (im - 0.5 * (ia + ib)).abs() > tol {
+            points.push((vm, im));
+            pending.push_back(((va, ia), (vm, im)));
+            pending.push_back(((vm, im), (vb, ib)));
+        }
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let (v, i): (Vec<f64>, Vec<f64>) = points.into_iter().unzip();
+    return Ok(IvCurve { v, i, isc, voc, mpp });
+}
@@ -0,0 +1,75 @@
+use crate::error::SolverError;
+use crate::stats::SolverStats;
+use crate::irradiance::PoaIrradiance;
+use crate::thermal::{Ambient, ThermalModel};
+
+/// Two-terminal element of an array, implemented by `PvCell`, `Series`, `Parallel` and `Module`
+/// so that topologies nest to any depth: `Series<Parallel<Series>>`, `Parallel<Series<Module>>`, ...
+///
+/// `strict`: fail on a non-converged solve; otherwise log it and return its last iterate (never `Err`).
+pub trait Element: Clone {
+    /// `PvCellState` for a cell, one state per child for the composite elements.
+    type State: Clone;
+
+    /// State under uniform irradiance [W/m2] and cell temperature [C].
+    fn compute_state(&self, irrad_ef: f64, cell_temp: f64) -> Self::State;
+
+    /// State under the plane-of-array irradiance of [`crate::Transposition::poa`], all components included.
+    fn compute_state_poa(&self, poa: &PoaIrradiance, cell_temp: f64) -> Self::State {
+        return self.compute_state(poa.global(), cell_temp);
+    }
+
+    /// State from weather data, with the cell temperature given by `thermal` at the POA irradiance.
+    fn compute_state_ambient(&self, poa: &PoaIrradiance, ambient: &Ambient, thermal: &ThermalModel) -> Self::State {
+        return self.compute_state_poa(poa, thermal.cell_temperature(poa.global(), ambient));
+    }
+
+    /// Current [A] at the terminal voltage `v` [V].
+    fn current_at(&self, state: &Self::State, v: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError>;
+
+    /// Voltage [V] at the terminal current `i` [A].
+    fn voltage_at(&self, state: &Self::State, i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError>;
+
+    /// dI/dV [S] at the operating point (`v`, `i`).
+    fn conductance_at(&self, state: &Self::State, v: f64, i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError>;
+
+    /// Open-circuit voltage [V] at reference conditions, from the parameters only (initial guesses).
+    fn nominal_voc(&self) -> f64;
+
+    /// Light current [A] at reference conditions, from the parameters only (initial guesses).
+    fn nominal_il(&self) -> f64;
+
+    /// Intervals of the P-V sweep of the global MPP search.
+    fn mpp_grid(&self) -> usize;
+
+    /// Equivalent element with its equivalent children merged.
+    fn reduced(&self) -> Self;
+
+    /// `Some(k)` when `other` behaves as `k` copies of `self` in series (`k` times the voltage at any current).
+    fn series_ratio(&self, other: &Self) -> Option<f64>;
+
+    /// `Some(k)` when `other` behaves as `k` copies of `self` in parallel (`k` times the current at any voltage).
+    fn parallel_ratio(&self, other: &Self) -> Option<f64>;
+
+    /// Absorbs `other`, connected in series, if the merged element is exact; returns whether it did.
+    fn merge_series(&mut self, other: &Self) -> bool;
+
+    /// Absorbs `other`, connected in parallel, if the merged element is exact; returns whether it did.
+    fn merge_parallel(&mut self, other: &Self) -> bool;
+}
+
+/// Common ratio of the element-wise `ratio` of two lists of children; `None` if any pair has none or they differ.
+pub(crate) fn common_ratio<E: Element>(a: &[E], b: &[E], ratio: impl Fn(&E, &E) -> Option<f64>) -> Option<f64> {
+    if a.is_empty() || a.len() != b.len() {
+        return None;
+    }
+    let mut common: Option<f64> = None;
+    for (x, y) in a.iter().zip(b.iter()) {
+        let k = ratio(x, y)?;
+        if common.is_some_and(|c| c != k) {
+            return None;
+        }
+        common = Some(k);
+    }
+    return common;
+}
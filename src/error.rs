@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fmt;
+
+/// Input of a solve: the imposed terminal voltage or current.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OperatingPoint {
+    Voltage(f64), // [V]
+    Current(f64), // [A]
+    Network,      // netlist solve: imposed by the sources of the netlist
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    /// iteration limit reached with `residual` still above `tol`; `last` is the last iterate
+    NotConverged {
+        iterations: usize,
+        residual: f64,
+        tol: f64,
+        input: OperatingPoint,
+        last: f64,
+    },
+    /// the iteration diverged to NaN or infinity
+    NonFinite {
+        iterations: usize,
+        input: OperatingPoint,
+    },
+}
+
+impl SolverError {
+    pub(crate) fn not_converged(iterations: usize, residual: f64, tol: f64, input: OperatingPoint, last: f64) -> SolverError {
+        if last.is_finite() {
+            return SolverError::NotConverged { iterations, residual, tol, input, last };
+        }
+        return SolverError::NonFinite { iterations, input };
+    }
+
+    pub fn iterations(&self) -> usize {
+        match self {
+            SolverError::NotConverged { iterations, .. } => *iterations,
+            SolverError::NonFinite { iterations, .. } => *iterations,
+        }
+    }
+
+    pub fn input(&self) -> OperatingPoint {
+        match self {
+            SolverError::NotConverged { input, .. } => *input,
+            SolverError::NonFinite { input, .. } => *input,
+        }
+    }
+
+    /// Value the solver ended with (`NaN` if it diverged).
+    pub fn last_value(&self) -> f64 {
+        match self {
+            SolverError::NotConverged { last, .. } => *last,
+            SolverError::NonFinite { .. } => f64::NAN,
+        }
+    }
+}
+
+impl fmt::Display for OperatingPoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OperatingPoint::Voltage(v) => write!(f, "v={:e}", v),
+            OperatingPoint::Current(i) => write!(f, "i={:e}", i),
+            OperatingPoint::Network => write!(f, "rede"),
+        }
+    }
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolverError::NotConverged { iterations, residual, tol, input, last } => write!(f,
+                "solver nao convergiu ({}): residual={:e} > tol={:e} apos {} iteracoes (ultimo valor {})",
+                input, residual, tol, iterations, last),
+            SolverError::NonFinite { iterations, input } => write!(f,
+                "solver divergiu ({}): valor nao finito apos {} iteracoes", input, iterations),
+        }
+    }
+}
+
+impl Error for SolverError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FitError {
+    /// inconsistent or out-of-range input data
+    InvalidInput(String),
+    /// the least-squares iteration stopped with `residual` (RMS of the normalized equations) above tolerance
+    NotConverged {
+        iterations: usize,
+        residual: f64,
+    },
+    /// the fit converged to parameters without physical meaning (e.g. negative series resistance)
+    NonPhysical(String),
+}
+
+impl fmt::Display for FitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FitError::InvalidInput(msg) => write!(f, "dados de entrada invalidos: {}", msg),
+            FitError::NotConverged { iterations, residual } => write!(f,
+                "ajuste nao convergiu: residual={:e} apos {} iteracoes", residual, iterations),
+            FitError::NonPhysical(msg) => write!(f, "ajuste sem significado fisico: {}", msg),
+        }
+    }
+}
+
+impl Error for FitError {}
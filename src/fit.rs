@@ -0,0 +1,235 @@
+use crate::error::FitError;
+use crate::pvcell::{BasicParams, PvCell, PvCellMethod, PvCellSolver, PvCellState, C_TO_K, S_REF, T_REF};
+use crate::lsq::{LsqResult, levenberg_marquardt};
+use crate::linalg::{invert, normal_equations};
+
+const K_Q: f64 = 1.38064852e-23 / 1.60217663e-19; // [V/K] thermal voltage per kelvin
+const DT_VOC: f64 = 10.0;    // [K] temperature step used to impose the Voc coefficient
+const TOL_COST: f64 = 1e-22; // sum of squared normalized residuals
+const MAX_COST: f64 = 1e-12; // above it the datasheet is considered inconsistent with the model
+const MAX_ITER: usize = 500;
+const IDEALITY_GUESSES: [f64; 4] = [1.0, 1.3, 1.6, 2.0];
+
+/// STC values of a module datasheet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Datasheet {
+    pub isc: f64,       // [A]   short-circuit current
+    pub voc: f64,       // [V]   open-circuit voltage
+    pub imp: f64,       // [A]   current at the maximum power point
+    pub vmp: f64,       // [V]   voltage at the maximum power point
+    pub alpha_sc: f64,  // [A/K] temperature coefficient of Isc
+    pub beta_voc: f64,  // [V/K] temperature coefficient of Voc
+    pub cells_in_series: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasheetFit {
+    pub params: BasicParams,
+    /// residuals of the five equations at the solution:
+    /// [A] Isc, [A] Voc, [A] MPP, [A] dP/dV = 0 at the MPP, [V] Voc at 25 + 10 °C
+    pub residuals: [f64; 5],
+    pub iterations: usize,
+}
+
+impl Datasheet {
+    fn validate(&self) -> Result<(), FitError> {
+        let values = [self.isc, self.voc, self.imp, self.vmp, self.alpha_sc, self.beta_voc];
+        if values.iter().any(|x| !x.is_finite()) {
+            return Err(FitError::InvalidInput("valores nao finitos".to_string()));
+        }
+        if self.isc <= 0.0 || self.voc <= 0.0 || self.imp <= 0.0 || self.vmp <= 0.0 || self.cells_in_series == 0 {
+            return Err(FitError::InvalidInput("Isc, Voc, Imp, Vmp e celulas em serie devem ser positivos".to_string()));
+        }
+        if self.imp >= self.isc || self.vmp >= self.voc {
+            return Err(FitError::InvalidInput(format!("MPP ({}, {}) fora do retangulo Isc x Voc ({}, {})",
+                self.vmp, self.imp, self.voc, self.isc)));
+        }
+        if self.beta_voc >= 0.0 {
+            return Err(FitError::InvalidInput(format!("beta_voc={} deve ser negativo", self.beta_voc)));
+        }
+        let ff: f64 = self.vmp * self.imp / (self.voc * self.isc);
+        if ff < 0.25 {
+            return Err(FitError::InvalidInput(format!("fator de forma {} incompativel com o modelo de um diodo", ff)));
+        }
+        return Ok(());
+    }
+
+    /// Five-equation residuals (Isc, Voc, MPP, dP/dV = 0, Voc(T)) in physical units.
+    fn equations(&self, p: &BasicParams) -> [f64; 5] {
+        let (a, il, i0, rs, rsh) = (p.a_ref, p.i_l_ref, p.i_o_ref, p.r_s, p.r_sh_ref);
+        let f_sc: f64 = il - i0 * (self.isc * rs / a).exp_m1() - self.isc * rs / rsh - self.isc;
+        let f_oc: f64 = il - i0 * (self.voc / a).exp_m1() - self.voc / rsh;
+        let vj: f64 = self.vmp + self.imp * rs;
+        let f_mp: f64 = il - i0 * (vj / a).exp_m1() - vj / rsh - self.imp;
+        let g: f64 = i0 / a * (vj / a).exp() + 1.0 / rsh;
+        let f_dp: f64 = self.imp - self.vmp * g / (1.0 + rs * g);
+
+        let cell = PvCell::new(p).with_solver(PvCellSolver { method: PvCellMethod::LambertW, ..PvCellSolver::default() });
+        let state = cell.compute_state(S_REF, T_REF - C_TO_K + DT_VOC);
+        let voc_t: f64 = cell.try_v_from_i(&state, 0.0).unwrap_or(f64::NAN);
+        let f_t: f64 = voc_t - (self.voc + self.beta_voc * DT_VOC);
+        return [f_sc, f_oc, f_mp, f_dp, f_t];
+    }
+
+    /// Parameters from `x = [a, r_s, ln(r_sh)]`; `i_o_ref` and `i_l_ref` are eliminated with the
+    /// Isc and Voc equations.
+    fn params(&self, x: &[f64]) -> BasicParams {
+        let (a, rs, rsh) = (x[0], x[1], x[2].exp());
+        let i0: f64 = (self.isc * (1.0 + rs / rsh) - self.voc / rsh) / ((self.voc / a).exp() - (self.isc * rs / a).exp());
+        let il: f64 = i0 * (self.voc / a).exp_m1() + self.voc / rsh;
+        BasicParams {
+            a_ref: a, i_l_ref: il, i_o_ref: i0, r_s: rs, r_sh_ref: rsh,
+            alpha_sc: self.alpha_sc, v_oc_ref: self.voc,
+        }
+    }
+}
+
+/// Single-diode parameters at reference conditions from datasheet values, in the style of
+/// the De Soto (CEC) method: Isc, Voc, the MPP, the zero power derivative at the MPP and the
+/// Voc temperature coefficient give five equations for `a_ref`, `i_l_ref`, `i_o_ref`, `r_s` and `r_sh_ref`.
+pub fn fit_datasheet(ds: &Datasheet) -> Result<DatasheetFit, FitError> {
+    ds.validate()?;
+
+    // chutes iniciais: fatores de idealidade típicos, Rs pela distância entre MPP e Voc
+    let vt: f64 = (ds.cells_in_series as f64) * K_Q * T_REF;
+    let rs0: f64 = 0.1 * (ds.voc - ds.vmp) / ds.imp;
+    let rsh0: f64 = 100.0 * ds.voc / ds.isc;
+    let scale = [ds.isc, ds.isc, ds.voc];
+
+    let mut best: Option<LsqResult> = None;
+    for n in IDEALITY_GUESSES {
+        let x0 = [n * vt, rs0, rsh0.ln()];
+        let res = levenberg_marquardt(|x: &[f64]| {
+            let f = ds.equations(&ds.params(x));
+            f[2..].iter().zip(scale.iter()).map(|(fi, s)| fi / s).collect()
+        }, &x0, TOL_COST, MAX_ITER);
+        let better = match &best {
+            None => true,
+            Some(b) => res.cost < b.cost,
+        };
+        if better {
+            best = Some(res);
+        }
+        if best.as_ref().is_some_and(|b| b.cost <= TOL_COST) {
+            break;
+        }
+    }
+    let Some(res) = best else {
+        return Err(FitError::NotConverged { iterations: 0, residual: f64::NAN });
+    };
+
+    if !res.converged || res.cost > MAX_COST {
+        return Err(FitError::NotConverged { iterations: res.iterations, residual: (res.cost / 3.0).sqrt() });
+    }
+    let params: BasicParams = ds.params(&res.x);
+    if params.a_ref <= 0.0 || params.i_l_ref <= 0.0 || params.i_o_ref <= 0.0 {
+        return Err(FitError::NonPhysical(format!("a_ref={}, i_l_ref={}, i_o_ref={}", params.a_ref, params.i_l_ref, params.i_o_ref)));
+    }
+    if params.r_s < 0.0 {
+        return Err(FitError::NonPhysical(format!("r_s={} negativa", params.r_s)));
+    }
+    let residuals = ds.equations(&params);
+    return Ok(DatasheetFit { params, residuals, iterations: res.iterations });
+}
+
+/// Value of a fitted parameter and its asymptotic 95 % confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamEstimate {
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+/// Single-diode parameters at the measurement conditions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IvFitParams {
+    pub il: ParamEstimate,      // [A]   photocurrent
+    pub i0: ParamEstimate,      // [A]   saturation current
+    pub a: ParamEstimate,       // [V]   modified ideality factor (1 / PvCellState::ra)
+    pub r_s: ParamEstimate,     // [Ohm]
+    pub r_sh: ParamEstimate,    // [Ohm] (1 / PvCellState::gsh)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IvFit {
+    pub cell: PvCell,           // fitted cell, parameters translated to reference conditions
+    pub state: PvCellState,     // fitted state at the measurement conditions
+    pub params: IvFitParams,
+    pub rmse: f64,              // [A] root mean square current residual
+    pub r_squared: f64,
+    pub iterations: usize,
+}
+
+const Z_95: f64 = 1.959964;   // two-sided 95 % quantile of the normal distribution
+const IV_TOL_COST: f64 = 1e-24;
+const IV_MIN_POINTS: usize = 8;
+
+/// Fits the single-diode parameters to measured `(v, i)` points taken at `irrad_ef` and
+/// `cell_temp`, and translates them back to reference conditions with [`PvCell::with_state`].
+///
+/// `template` supplies the starting point (its state at the measurement conditions), the
+/// temperature coefficients and the `ns`/`np` of the measured device. Points in reverse bias
+/// (`v < 0`) are ignored. The fitted cell is single-diode: a recombination diode in the
+/// template is dropped.
+pub fn fit_iv_curve(points: &[(f64, f64)], irrad_ef: f64, cell_temp: f64, template: &PvCell) -> Result<IvFit, FitError> {
+    let ns: f64 = template.ns as f64;
+    let np: f64 = template.np as f64;
+    let data: Vec<(f64, f64)> = points.iter()
+        .filter(|(v, i)| v.is_finite() && i.is_finite() && *v >= 0.0)
+        .map(|(v, i)| (v / ns, i / np))
+        .collect();
+    if data.len() < IV_MIN_POINTS {
+        return Err(FitError::InvalidInput(format!("{} pontos validos; minimo {}", data.len(), IV_MIN_POINTS)));
+    }
+    if irrad_ef.is_nan() || irrad_ef <= 0.0 || !cell_temp.is_finite() {
+        return Err(FitError::InvalidInput(format!("condicoes de medicao invalidas ({}, {})", irrad_ef, cell_temp)));
+    }
+    let guess = template.compute_state(irrad_ef, cell_temp);
+    if !(guess.il > 0.0 && guess.i0 > 0.0 && guess.ra > 0.0 && guess.gsh > 0.0 && template.r_s >= 0.0) {
+        return Err(FitError::InvalidInput("template sem parametros basicos validos".to_string()));
+    }
+
+    // x = [il, ln(i0), a, r_s, ln(r_sh)]
+    let x0 = [guess.il, guess.i0.ln(), 1.0 / guess.ra, template.r_s.max(1e-3), (1.0 / guess.gsh).ln()];
+    let cell = PvCell { ns: 1, np: 1, v_bypass: f64::NEG_INFINITY, ..template.clone() }
+        .with_solver(PvCellSolver { method: PvCellMethod::LambertW, ..PvCellSolver::default() });
+    let model = |x: &[f64]| -> Vec<f64> {
+        let state = PvCellState { gsh: (-x[4]).exp(), ra: 1.0 / x[2], i0: x[1].exp(), il: x[0], i02: 0.0, ra2: 0.0, bypass: None };
+        let cell = PvCell { r_s: x[3], ..cell.clone() };
+        data.iter().map(|&(v, i)| cell.try_solve_i(&state, v).unwrap_or(f64::NAN) - i).collect()
+    };
+    let res = levenberg_marquardt(model, &x0, IV_TOL_COST, MAX_ITER);
+    if !res.converged || !res.cost.is_finite() {
+        return Err(FitError::NotConverged { iterations: res.iterations, residual: (res.cost / data.len() as f64).sqrt() });
+    }
+    let x = &res.x;
+    if x[2] <= 0.0 || x[0] <= 0.0 {
+        return Err(FitError::NonPhysical(format!("il={}, a={}", x[0], x[2])));
+    }
+    if x[3] < 0.0 {
+        return Err(FitError::NonPhysical(format!("r_s={} negativa", x[3])));
+    }
+
+    // qualidade do ajuste
+    let n: usize = data.len();
+    let ssr: f64 = res.cost;
+    let mean: f64 = data.iter().map(|(_, i)| i).sum::<f64>() / n as f64;
+    let sst: f64 = data.iter().map(|(_, i)| (i - mean).powi(2)).sum();
+    let rmse: f64 = (ssr / n as f64).sqrt() * np;
+    let r_squared: f64 = 1.0 - ssr / sst;
+
+    // intervalos assintóticos: cov = s^2 (J^T J)^-1
+    let (jtj, _) = normal_equations(&res.jacobian, &res.residuals);
+    let dof: f64 = (n - x.len()).max(1) as f64;
+    let half: Vec<f64> = match invert(&jtj) {
+        Some(inv) => (0..x.len()).map(|k| Z_95 * (ssr / dof * inv[k][k]).max(0.0).sqrt()).collect(),
+        None => vec![f64::INFINITY; x.len()],
+    };
+    let linear = |k: usize| ParamEstimate { value: x[k], low: x[k] - half[k], high: x[k] + half[k] };
+    let log = |k: usize| ParamEstimate { value: x[k].exp(), low: (x[k] - half[k]).exp(), high: (x[k] + half[k]).exp() };
+    let params = IvFitParams { il: linear(0), i0: log(1), a: linear(2), r_s: linear(3), r_sh: log(4) };
+
+    let state = PvCellState { gsh: 1.0 / params.r_sh.value, ra: 1.0 / params.a.value, i0: params.i0.value, il: params.il.value, i02: 0.0, ra2: 0.0, bypass: None };
+    let cell = template.clone().with_state(&state, params.r_s.value, irrad_ef, cell_temp);
+    return Ok(IvFit { cell, state, params, rmse, r_squared, iterations: res.iterations });
+}
@@ -0,0 +1,179 @@
+/// Solar constant [W/m2], default extraterrestrial normal irradiance.
+pub const SOLAR_CONSTANT: f64 = 1361.0;
+
+/// Perez brightness bins: upper limits of `eps` for the first seven bins.
+const PEREZ_EPS_BINS: [f64; 7] = [1.065, 1.23, 1.5, 1.95, 2.8, 4.5, 6.2];
+
+/// Perez (1990) coefficients, all sites composite: `[f11, f12, f13]` per brightness bin.
+const PEREZ_F1: [[f64; 3]; 8] = [
+    [-0.008, 0.588, -0.062],
+    [0.130, 0.683, -0.151],
+    [0.330, 0.487, -0.221],
+    [0.568, 0.187, -0.295],
+    [0.873, -0.392, -0.362],
+    [1.132, -1.237, -0.412],
+    [1.060, -1.600, -0.359],
+    [0.678, -0.327, -0.250],
+];
+
+/// Perez (1990) coefficients, all sites composite: `[f21, f22, f23]` per brightness bin.
+const PEREZ_F2: [[f64; 3]; 8] = [
+    [-0.060, 0.072, -0.022],
+    [-0.019, 0.066, -0.029],
+    [0.055, -0.064, -0.026],
+    [0.109, -0.152, -0.014],
+    [0.226, -0.462, 0.001],
+    [0.288, -0.823, 0.056],
+    [0.264, -1.127, 0.131],
+    [0.156, -1.377, 0.251],
+];
+
+/// Diffuse sky model of [`Transposition`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SkyModel {
+    #[default]
+    Isotropic,  // uniform sky
+    HayDavies,  // circumsolar share given by the anisotropy index DNI/DNI_extra
+    Perez,      // circumsolar and horizon brightening, 1990 all-sites coefficients
+}
+
+/// Orientation of the module plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Surface {
+    pub tilt: f64,      // [deg] from horizontal
+    pub azimuth: f64,   // [deg] clockwise from north (180: facing south)
+}
+
+impl Surface {
+    pub fn new(tilt: f64, azimuth: f64) -> Self {
+        return Surface { tilt, azimuth };
+    }
+
+    /// Cosine of the angle of incidence of the beam for the sun at `zenith`, `azimuth` [deg].
+    pub fn cos_aoi(&self, zenith: f64, azimuth: f64) -> f64 {
+        let (t, z): (f64, f64) = (self.tilt.to_radians(), zenith.to_radians());
+        let cos_aoi: f64 = z.cos() * t.cos() + z.sin() * t.sin() * (azimuth - self.azimuth).to_radians().cos();
+        return cos_aoi.clamp(-1.0, 1.0);
+    }
+
+    /// Angle of incidence [deg] of the beam.
+    pub fn aoi(&self, zenith: f64, azimuth: f64) -> f64 {
+        return self.cos_aoi(zenith, azimuth).acos().to_degrees();
+    }
+}
+
+/// Irradiance components on the horizontal and normal planes [W/m2].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyIrradiance {
+    pub ghi: f64,       // global horizontal
+    pub dni: f64,       // direct normal
+    pub dhi: f64,       // diffuse horizontal
+    pub dni_extra: f64, // extraterrestrial normal (Hay-Davies and Perez)
+}
+
+impl SkyIrradiance {
+    /// With `dni_extra` = [`SOLAR_CONSTANT`].
+    pub fn new(ghi: f64, dni: f64, dhi: f64) -> Self {
+        return SkyIrradiance { ghi, dni, dhi, dni_extra: SOLAR_CONSTANT };
+    }
+
+    /// builder
+    pub fn with_dni_extra(mut self, dni_extra: f64) -> Self { self.dni_extra = dni_extra; return self; }
+}
+
+/// Plane-of-array irradiance [W/m2], by component.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PoaIrradiance {
+    pub beam: f64,
+    pub sky_diffuse: f64,
+    pub ground: f64,    // reflected by the ground
+}
+
+impl PoaIrradiance {
+    pub fn global(&self) -> f64 {
+        return self.beam + self.sky_diffuse + self.ground;
+    }
+
+    pub fn diffuse(&self) -> f64 {
+        return self.sky_diffuse + self.ground;
+    }
+}
+
+/// Kasten-Young (1989) relative air mass at the apparent solar `zenith` [deg]; `NaN` below the horizon.
+pub fn relative_airmass(zenith: f64) -> f64 {
+    if !(0.0..90.0).contains(&zenith) {
+        return f64::NAN;
+    }
+    return 1.0 / (zenith.to_radians().cos() + 0.50572 * (96.07995 - zenith).powf(-1.6364));
+}
+
+/// Absolute (pressure-corrected) air mass at the site `pressure` [mbar].
+pub fn absolute_airmass(relative: f64, pressure: f64) -> f64 {
+    return relative * pressure / 1013.25;
+}
+
+/// Transposition of GHI/DNI/DHI to the plane of array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transposition {
+    pub model: SkyModel,
+    pub albedo: f64,    // [-] ground reflectance
+}
+
+impl Default for Transposition {
+    fn default() -> Self {
+        Transposition { model: SkyModel::Isotropic, albedo: 0.25 }
+    }
+}
+
+impl Transposition {
+    pub fn new(model: SkyModel, albedo: f64) -> Self {
+        return Transposition { model, albedo };
+    }
+
+    /// POA components for the sun at `zenith`, `azimuth` [deg]; the beam is zero with the sun
+    /// behind the plane or below the horizon.
+    pub fn poa(&self, surface: &Surface, zenith: f64, azimuth: f64, sky: &SkyIrradiance) -> PoaIrradiance {
+        let cos_aoi: f64 = surface.cos_aoi(zenith, azimuth);
+        let beam: f64 = if zenith < 90.0 { (sky.dni * cos_aoi).max(0.0) } else { 0.0 };
+        let ground: f64 = sky.ghi * self.albedo * (1.0 - surface.tilt.to_radians().cos()) / 2.0;
+        let sky_diffuse: f64 = match self.model {
+            SkyModel::Isotropic => isotropic(surface, sky.dhi),
+            SkyModel::HayDavies => hay_davies(surface, zenith, cos_aoi, sky),
+            SkyModel::Perez => perez(surface, zenith, cos_aoi, sky),
+        };
+        return PoaIrradiance { beam, sky_diffuse, ground };
+    }
+}
+
+fn isotropic(surface: &Surface, dhi: f64) -> f64 {
+    return dhi * (1.0 + surface.tilt.to_radians().cos()) / 2.0;
+}
+
+fn hay_davies(surface: &Surface, zenith: f64, cos_aoi: f64, sky: &SkyIrradiance) -> f64 {
+    // índice de anisotropia e razão geométrica do feixe (cos z limitado a 89°)
+    let a: f64 = (sky.dni / sky.dni_extra).clamp(0.0, 1.0);
+    let rb: f64 = cos_aoi.max(0.0) / zenith.to_radians().cos().max(0.01745);
+    return sky.dhi * (a * rb + (1.0 - a) * (1.0 + surface.tilt.to_radians().cos()) / 2.0);
+}
+
+fn perez(surface: &Surface, zenith: f64, cos_aoi: f64, sky: &SkyIrradiance) -> f64 {
+    let airmass: f64 = relative_airmass(zenith);
+    if sky.dhi <= 0.0 || airmass.is_nan() {
+        return 0.0;
+    }
+    const KAPPA: f64 = 1.041;  // para o zênite em radianos
+    let z: f64 = zenith.to_radians();
+    let z3: f64 = KAPPA * z.powi(3);
+    let eps: f64 = ((sky.dhi + sky.dni) / sky.dhi + z3) / (1.0 + z3);
+    let delta: f64 = sky.dhi * airmass / sky.dni_extra;
+    let bin: usize = PEREZ_EPS_BINS.iter().take_while(|&&limit| eps >= limit).count();
+    let (c1, c2) = (&PEREZ_F1[bin], &PEREZ_F2[bin]);
+    let f1: f64 = (c1[0] + c1[1] * delta + c1[2] * z).max(0.0);
+    let f2: f64 = c2[0] + c2[1] * delta + c2[2] * z;
+
+    let t: f64 = surface.tilt.to_radians();
+    let a: f64 = cos_aoi.max(0.0);
+    let b: f64 = z.cos().max(85f64.to_radians().cos());
+    let sky_diffuse: f64 = sky.dhi * ((1.0 - f1) * (1.0 + t.cos()) / 2.0 + f1 * a / b + f2 * t.sin());
+    return sky_diffuse.max(0.0);
+}
@@ -0,0 +1,65 @@
+use std::f64::consts::E;
+
+const MAX_ITER: usize = 50;
+const EXP_FORM_LIMIT: f64 = 100.0; // above it W(exp(theta)) is solved in log form
+
+/// Principal branch W0(x) of the Lambert W function, defined for x >= -1/e.
+pub(crate) fn lambert_w0(x: f64) -> f64 {
+    if x.is_nan() || x < -1.0 / E {
+        return f64::NAN;
+    }
+    if x == 0.0 || x.is_infinite() {
+        return x;
+    }
+
+    // chute inicial
+    let mut w: f64 = if x < -0.32 {
+        let p: f64 = (2.0 * (E * x + 1.0)).max(0.0).sqrt();  // série no ponto de ramificação
+        -1.0 + p - p * p / 3.0 + 11.0 / 72.0 * p * p * p
+    } else if x < 3.0 {
+        x.ln_1p() * (1.0 - x.ln_1p() / (2.0 + x.ln_1p()))
+    } else {
+        let l1: f64 = x.ln();
+        let l2: f64 = l1.ln();
+        l1 - l2 + l2 / l1
+    };
+
+    // Halley
+    for _ in 0..MAX_ITER {
+        let ew: f64 = w.exp();
+        let f: f64 = w * ew - x;
+        let wp1: f64 = w + 1.0;
+        if wp1 == 0.0 {
+            break;
+        }
+        let dw: f64 = f / (ew * wp1 - (w + 2.0) * f / (2.0 * wp1));
+        w -= dw;
+        if dw.abs() <= 4.0 * f64::EPSILON * (1.0 + w.abs()) {
+            break;
+        }
+    }
+    return w;
+}
+
+/// W0(exp(theta)), evaluated as the root of `w + ln(w) = theta` when exp(theta) would overflow.
+pub(crate) fn lambert_w0_exp(theta: f64) -> f64 {
+    if theta.is_nan() {
+        return f64::NAN;
+    }
+    if theta < EXP_FORM_LIMIT {
+        return lambert_w0(theta.exp());
+    }
+    if theta.is_infinite() {
+        return theta;
+    }
+
+    let mut w: f64 = theta - theta.ln();
+    for _ in 0..MAX_ITER {
+        let dw: f64 = (w + w.ln() - theta) / (1.0 + 1.0 / w);
+        w -= dw;
+        if dw.abs() <= 4.0 * f64::EPSILON * w {
+            break;
+        }
+    }
+    return w;
+}
@@ -1,6 +1,83 @@
+//! Photovoltaic cell, string and array models based on the single-diode equation.
+//!
+//! The public API is re-exported at the crate root; `use libPvRust::prelude::*;`
+//! brings the most common types into scope.
+//!
+//! ```
+//! use libPvRust::prelude::*;
+//!
+//! let params = BasicParams {
+//!     a_ref: 1.81, i_o_ref: 8.5e-11, i_l_ref: 7.4, r_s: 0.6,
+//!     r_sh_ref: 600.0, alpha_sc: 3.8e-3, v_oc_ref: 48.6,
+//! };
+//! let string = Series::new(vec![PvCell::new(&params); 10]);
+//! let states = string.states_uniform_conditions(800.0, 40.0);
+//! let i = string.i_from_v(&states, 300.0);
+//! assert!(i > 0.0);
+//! ```
+
+#![allow(non_snake_case)]            // crate name
+#![allow(clippy::needless_return)]   // explicit returns are the house style
+
+mod error;
+mod stats;
+mod lambertw;
+mod roots;
+mod element;
+mod mpp;
+mod small_signal;
+mod curve;
+mod linalg;
+mod lsq;
+mod fit;
 mod pvcell;
 mod series;
 mod parallel;
+mod module;
+mod netlist;
+mod spice;
+mod irradiance;
+mod solar;
+mod thermal;
+mod optics;
+
+pub use error::{FitError, OperatingPoint, SolverError};
+pub use stats::{SolverCounters, SolverStats};
+pub use mpp::Mpp;
+pub use small_signal::SmallSignal;
+pub use element::Element;
+pub use curve::{CurveOptions, IvCurve};
+pub use fit::{Datasheet, DatasheetFit, IvFit, IvFitParams, ParamEstimate, fit_datasheet, fit_iv_curve};
+pub use pvcell::{BasicParams, Breakdown, BypassDiode, PvCell, PvCellMethod, PvCellSolver, PvCellState};
+pub use series::{Series, SeriesMethod, SeriesSolver};
+pub use parallel::{Parallel, ParallelMap, ParallelSolver};
+pub use module::{Module, ModuleSolver, Substring, SubstringState};
+pub use spice::SpiceExport;
+pub use irradiance::{PoaIrradiance, SOLAR_CONSTANT, SkyIrradiance, SkyModel, Surface, Transposition, absolute_airmass, relative_airmass};
+pub use netlist::{Component, GROUND, Netlist, NetlistSolution, NetlistSolver, NetlistState, NodeId};
+pub use solar::{DELTA_T_DEFAULT, Site, SolarAlgorithm, SolarPosition, UtcTime, extraterrestrial_irradiance, solar_position};
+pub use thermal::{Ambient, ThermalModel, TransientThermal};
+pub use optics::{Iam, OpticalLosses, SpectralModel};
+
+pub mod prelude {
+    pub use crate::error::{FitError, OperatingPoint, SolverError};
+    pub use crate::stats::{SolverCounters, SolverStats};
+    pub use crate::mpp::Mpp;
+    pub use crate::small_signal::SmallSignal;
+    pub use crate::element::Element;
+    pub use crate::curve::{CurveOptions, IvCurve};
+    pub use crate::fit::{Datasheet, DatasheetFit, IvFit, IvFitParams, ParamEstimate, fit_datasheet, fit_iv_curve};
+    pub use crate::pvcell::{BasicParams, Breakdown, BypassDiode, PvCell, PvCellMethod, PvCellSolver, PvCellState};
+    pub use crate::series::{Series, SeriesMethod, SeriesSolver};
+    pub use crate::parallel::{Parallel, ParallelMap, ParallelSolver};
+    pub use crate::module::{Module, ModuleSolver, Substring, SubstringState};
+    pub use crate::spice::SpiceExport;
+    pub use crate::irradiance::{PoaIrradiance, SkyIrradiance, SkyModel, Surface, Transposition};
+    pub use crate::netlist::{Component, GROUND, Netlist, NetlistSolution, NetlistSolver, NetlistState, NodeId};
+    pub use crate::solar::{Site, SolarAlgorithm, SolarPosition, UtcTime, solar_position};
+    pub use crate::thermal::{Ambient, ThermalModel, TransientThermal};
+    pub use crate::optics::{Iam, OpticalLosses, SpectralModel};
+}
 
 #[cfg(test)]
 mod tests {
@@ -122,6 +199,7 @@ mod tests {
             max_iter: 100,
             tol_i: 0.001,
             tol_v: 0.01,
+            method: PvCellMethod::Newton,
         };
 
         let pnl0: PvCell = PvCell::new(&params0).with_ns(3).with_np(1).with_solver(solver_settings);
@@ -147,7 +225,7 @@ mod tests {
             pnl0.clone(),
             pnl0.clone(),
             pnl0.clone(),])
-            .with_solver(SeriesSolver {max_iter: 1000, tol_v: 1e-1, min_g: 0.0});
+            .with_solver(SeriesSolver {max_iter: 1000, tol_v: 1e-1, min_g: 0.0, ..SeriesSolver::default()});
         println!("\noriginal: {:?}", &string);
 
         let conditions = [(200.0, 60.0), (800.0, 30.0), (600.0, 25.0), (999.0, 45.0)];
@@ -176,6 +254,7 @@ mod tests {
             // tracing::subscriber::set_global_default(subscriber).expect("Erro ao configurar logger");
         info!("Log iniciado: {}", log_path.display());
 
+        let mut stats = SolverStats::new();
         let start = Instant::now();
         if reduce{
             info!("({:p}) Series::reduce()", &string);
@@ -193,8 +272,8 @@ mod tests {
                 let (irrad, temp) = cond;
                 let states: Vec<PvCellState> = string.states_uniform_conditions(irrad, temp);
                 for v in voltages {
-                    let i = string.i_from_v(&states, v);
-                    let v2 = string.v_from_i(&states, i);
+                    let i = string.i_from_v_with_stats(&states, v, &mut stats);
+                    let v2 = string.v_from_i_with_stats(&states, i, &mut stats);
                     sum_p += v2 * i;
                     // println!("({irrad}, {temp}): iters: {iter}   {v:.3} -> {i:.3} -> {v2:.3},     pot: {:.3}    dv: {:.3}", i * v2, (v - v2).abs())
                 }
@@ -202,26 +281,952 @@ mod tests {
         }
         let elapsed_time = start.elapsed().as_secs_f64();
 
-        unsafe{
-            let string_count = series::SOLVER_CALLS;
-            info!("string::SOLVER_CALLS: {}", string_count);
-
-            let panel_count = pvcell::SOLVER_CALLS;
-            info!("panel::SOLVER_CALLS: {}", panel_count);
-        }
+        info!("string solver: {:?}", stats.series);
+        info!("panel solver: {:?}", stats.cell);
         info!("elapsed time: {} s", &elapsed_time);
         info!("sum_p: {sum_p}");
         info!("Log encerrado");
         let file = std::fs::File::open(log_path).unwrap();
         let reader = io::BufReader::new(file);
 
-        for line in reader.lines() {
-            if let Ok(linha) = line {
-                println!("{}", linha);
-            }
+        for linha in reader.lines().map_while(Result::ok) {
+            println!("{}", linha);
         }
         });
         
         return sum_p;
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn solver_errors(){
+        let pnl = PvCell::new(&PARAMS);
+        let state = pnl.compute_state(800.0, 40.0);
+
+        let i = pnl.try_solve_i(&state, 30.0).unwrap();
+        assert_eq!(i, pnl.solve_i(&state, 30.0));
+        let v = pnl.try_v_from_i(&state, i).unwrap();
+        assert!((v - 30.0).abs() < 0.1);
+
+        let starved = pnl.clone().with_solver(PvCellSolver { max_iter: 1, ..PvCellSolver::default() });
+        match starved.try_solve_i(&state, 30.0) {
+            Err(SolverError::NotConverged { iterations, residual, tol, input, last }) => {
+                assert_eq!(iterations, 1);
+                assert!(residual > tol);
+                assert_eq!(input, OperatingPoint::Voltage(30.0));
+                assert_eq!(last, starved.solve_i(&state, 30.0));
+            }
+            other => panic!("esperado NotConverged, obtido {:?}", other),
+        }
+
+        let string = Series::new(vec![pnl; 4]).with_solver(SeriesSolver { max_iter: 2, ..SeriesSolver::default() });
+        let states = string.states_uniform_conditions(800.0, 40.0);
+        let err = string.try_i_from_v(&states, 120.0).unwrap_err();
+        assert_eq!(err.input(), OperatingPoint::Voltage(120.0));
+        assert_eq!(err.iterations(), 2);
+    }
+
+
+    #[test]
+    fn solver_stats_per_thread(){
+        let pnl = PvCell::new(&PARAMS);
+        let string = Series::new(vec![pnl; 6]);
+        let states = string.states_uniform_conditions(700.0, 35.0);
+        let voltages = [60.0, 120.0, 180.0, 240.0];
+
+        let mut sequential = SolverStats::new();
+        for v in voltages {
+            string.i_from_v_with_stats(&states, v, &mut sequential);
+        }
+        assert_eq!(sequential.series.calls, voltages.len());
+        assert_eq!(sequential.series.non_converged, 0);
+        assert!(sequential.cell.calls > sequential.series.iterations);
+        assert!(sequential.series.max_residual < string.solver.tol_v);
+
+        let mut merged = SolverStats::new();
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = voltages.iter().map(|&v| {
+                let (string, states) = (&string, &states);
+                scope.spawn(move || {
+                    let mut stats = SolverStats::new();
+                    string.i_from_v_with_stats(states, v, &mut stats);
+                    stats
+                })
+            }).collect();
+            for h in handles {
+                merged.merge(&h.join().unwrap());
+            }
+        });
+        assert_eq!(merged, sequential);
+
+        // um resíduo NaN é contado à parte e não apaga o máximo
+        let mut counters = SolverCounters::default();
+        counters.record(3, f64::NAN, false);
+        counters.record(2, 0.5, true);
+        let mut total = SolverCounters::default();
+        total.merge(&counters);
+        total.merge(&sequential.series);
+        assert_eq!((total.non_finite, total.non_converged, total.max_residual), (1, 1, 0.5));
+    }
+
+
+    #[test]
+    fn lambert_w(){
+        use crate::lambertw::{lambert_w0, lambert_w0_exp};
+
+        assert!((lambert_w0(std::f64::consts::E) - 1.0).abs() < 1e-15);
+        assert!((lambert_w0(-1.0 / std::f64::consts::E) + 1.0).abs() < 1e-7);
+        for x in [-0.3, -1e-3, 1e-10, 0.5, 2.0, 10.0, 1e5, 1e300] {
+            let w = lambert_w0(x);
+            assert!((w * w.exp() - x).abs() <= 1e-13 * x.abs(), "W({x}) = {w}");
+        }
+        for theta in [-50.0, 0.0, 99.0, 101.0, 1e3, 1e6] {
+            let w = lambert_w0_exp(theta);
+            assert!((w + w.ln() - theta).abs() <= 1e-12 * theta.abs().max(1.0), "W(exp({theta})) = {w}");
+        }
+    }
+
+    #[test]
+    fn lambert_matches_newton(){
+        let newton = PvCell::new(&PARAMS).with_ns(2).with_np(3)
+            .with_solver(PvCellSolver { tol_i: 1e-12, tol_v: 1e-12, ..PvCellSolver::default() });
+        let lambert = newton.clone().with_solver(PvCellSolver { method: PvCellMethod::LambertW, ..PvCellSolver::default() });
+
+        for (irrad, temp) in [(1000.0, 25.0), (200.0, 60.0), (50.0, -10.0)] {
+            let state = newton.compute_state(irrad, temp);
+            for v in [0.0, 20.0, 60.0, 80.0, 95.0] {
+                let i_n = newton.try_solve_i(&state, v).unwrap();
+                let i_l = lambert.try_solve_i(&state, v).unwrap();
+                assert!((i_n - i_l).abs() < 1e-9, "({irrad}, {temp}) v={v}: {i_n} != {i_l}");
+
+                let v_n = newton.try_v_from_i(&state, i_n).unwrap();
+                let v_l = lambert.try_v_from_i(&state, i_l).unwrap();
+                assert!((v_n - v_l).abs() < 1e-8 && (v_l - v).abs() < 1e-8, "({irrad}, {temp}) v={v}: {v_n} != {v_l}");
+            }
+            // polarização direta forte: exp(theta) estoura em f64
+            assert!(lambert.try_solve_i(&state, 2000.0).unwrap().is_finite());
+        }
+    }
+
+
+    #[test]
+    fn parallel_v_from_i(){
+        let pnl = PvCell::new(&PARAMS);
+        let mut shaded = Series::new(vec![pnl.clone(); 8]);
+        shaded.elements[2].shading = 0.6;
+        let array = Parallel::new(vec![
+            Series::new(vec![pnl.clone(); 8]),
+            shaded,
+            Series::new(vec![pnl.clone().with_np(2); 7]),
+        ]).with_solver(ParallelSolver { max_iter: 100, tol_i: 1e-3 });
+        let states = array.states_uniform_conditions(900.0, 45.0);
+
+        let mut stats = SolverStats::new();
+        for v in [50.0, 200.0, 300.0, 330.0] {
+            let i = array.try_i_from_v(&states, v).unwrap();
+            let v2 = array.try_v_from_i_with_stats(&states, i, &mut stats).unwrap();
+            let is = array.try_is_from_v(&states, v2).unwrap();
+            assert_eq!(is.len(), 3);
+            assert!((is.iter().sum::<f64>() - i).abs() < 1e-3, "v={v}: {is:?} != {i}");
+            assert!((v2 - v).abs() < 0.5, "v={v}: v2={v2}");
+        }
+        assert_eq!(stats.parallel.calls, 4);
+        assert_eq!(stats.parallel.non_converged, 0);
+
+        // uma string só: a chamada também é contada
+        let single = Parallel::new(vec![Series::new(vec![pnl; 8])]);
+        let v = single.try_v_from_i_with_stats(&single.states_uniform_conditions(900.0, 45.0), 5.0, &mut stats).unwrap();
+        assert!(v > 0.0 && stats.parallel.calls == 5);
+    }
+
+
+    #[test]
+    fn parallel_reduce_maps(){
+        let p = PvCell::new(&PARAMS).with_solver(PvCellSolver { tol_i: 1e-10, tol_v: 1e-10, ..PvCellSolver::default() });
+        let q = PvCell { a_ref: 1.92, ..p.clone() };
+        let solver = SeriesSolver { tol_v: 1e-8, ..SeriesSolver::default() };
+        let a = Series::new(vec![p.clone(), p.clone(), q.clone(), p.clone()]).with_solver(solver.clone());
+        let b = Series::new(vec![p.clone(), q.clone().with_np(2), p.clone()]).with_solver(solver);
+        let array = Parallel::new(vec![a.clone(), b.clone(), a]);
+
+        let (reduced, map) = array.reduce_map();
+        assert_eq!(reduced.len(), 2);
+        assert_eq!(map.origin_to_reduced, vec![0, 1, 0]);
+        assert_eq!(map.reduced_to_origin, vec![vec![0, 2], vec![1]]);
+        assert_eq!(map.elements_origin_to_reduced, vec![vec![0, 0, 1, 0], vec![0, 1, 0], vec![0, 0, 1, 0]]);
+
+        let v = 120.0;
+        let states = array.states_uniform_conditions(800.0, 40.0);
+        let states_r = reduced.states_uniform_conditions(800.0, 40.0);
+        let is_r = reduced.try_is_from_v(&states_r, v).unwrap();
+        let vs_r: Vec<Vec<f64>> = reduced.elements.iter().zip(&states_r).zip(&is_r)
+            .map(|((s, st), &i)| s.try_vs_from_i(st, i).unwrap()).collect();
+        let currents_r: Vec<Vec<f64>> = reduced.elements.iter().zip(&is_r).map(|(s, &i)| vec![i; s.len()]).collect();
+
+        let is = map.expand_string_currents(&is_r);
+        let vs = map.expand_voltages(&vs_r);
+        let currents = map.expand_currents(&currents_r);
+        let powers = map.expand_powers(&vs_r.iter().zip(&currents_r)
+            .map(|(v, i)| v.iter().zip(i).map(|(v, i)| v * i).collect()).collect::<Vec<Vec<f64>>>());
+        for (k, string) in array.elements.iter().enumerate() {
+            let i = string.try_i_from_v(&states[k], v).unwrap();
+            assert!((is[k] - i).abs() < 1e-6, "{k}: {} != {i}", is[k]);
+            for (m, v_m) in string.try_vs_from_i(&states[k], i).unwrap().into_iter().enumerate() {
+                assert!((vs[k][m] - v_m).abs() < 1e-6, "[{k}][{m}]: {} != {v_m}", vs[k][m]);
+                assert!((currents[k][m] - i).abs() < 1e-6);
+                assert!((powers[k][m] - v_m * i).abs() < 1e-4);
+            }
+        }
+    }
+
+
+    #[test]
+    fn mpp_partial_shading(){
+        let pnl = PvCell::new(&PARAMS);
+        let state = pnl.compute_state(1000.0, 25.0);
+        let cell = pnl.try_mpp(&state).unwrap();
+        let sweep = (0..=4000).map(|k| k as f64 * 0.0125).map(|v| v * pnl.solve_i(&state, v)).fold(0.0, f64::max);
+        assert!(cell.p >= sweep - 1e-3 && cell.p < sweep + 1e-2, "{cell:?} vs {sweep}");
+        assert!((cell.p - cell.v * cell.i).abs() < 1e-9);
+
+        let mut string = Series::new(vec![pnl.clone(); 8]);
+        for k in [1, 4, 6] {
+            string.elements[k].shading = 0.6;
+        }
+        let states = string.states_uniform_conditions(1000.0, 25.0);
+        let mpp = string.try_mpp(&states).unwrap();
+
+        // varredura densa: dois picos, o global em tensão alta (bypass inativo)
+        let v_oc = string.v_from_i(&states, 0.0);
+        let p: Vec<f64> = (0..=2000).map(|k| v_oc * k as f64 / 2000.0)
+            .map(|v| v * string.i_from_v(&states, v)).collect();
+        let peaks: Vec<f64> = (1..p.len() - 1).filter(|&k| p[k] > p[k - 1] && p[k] >= p[k + 1]).map(|k| p[k]).collect();
+        let p_max = p.iter().cloned().fold(0.0, f64::max);
+        assert!(peaks.len() >= 2, "{peaks:?}");
+        assert!(peaks.iter().any(|&pk| pk < 0.9 * p_max));
+        assert!(mpp.p >= p_max - 1.0, "{mpp:?} vs {p_max}");
+
+        let array = Parallel::new(vec![string.clone(), string]);
+        let (mpp_arr, _) = array.mpp_at(1000.0, 25.0);
+        assert!(mpp_arr.p > 1.9 * mpp.p - 1.0 && (mpp_arr.v - mpp.v).abs() < 1.0, "{mpp_arr:?} vs {mpp:?}");
+    }
+
+
+    #[test]
+    fn iv_curves(){
+        let pnl = PvCell::new(&PARAMS);
+        let state = pnl.compute_state(1000.0, 25.0);
+        let opts = CurveOptions::default();
+        let curve = pnl.try_iv_curve(&state, &opts.clone().with_reverse_bias(-5.0)).unwrap();
+        assert!(curve.v.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(curve.v[0], -5.0);
+        assert_eq!((*curve.v.last().unwrap(), *curve.i.last().unwrap()), (curve.voc, 0.0));
+        assert!(curve.len() > opts.n_points && curve.len() <= opts.max_points);
+        let ff = curve.fill_factor();
+        assert!(ff > 0.7 && ff < 0.85, "ff = {ff}");
+        assert!((curve.pmp() - curve.p().into_iter().fold(0.0, f64::max)).abs() < 1e-9);
+        // reversa: o bypass conduz abaixo de v_bypass
+        assert!(curve.i[0] > curve.isc + 1.0);
+
+        let mut string = Series::new(vec![pnl.clone(); 6]);
+        string.elements[0].shading = 0.5;
+        let states = string.states_uniform_conditions(1000.0, 25.0);
+        let curve = string.iv_curve(&states, &opts);
+        let tol = 5.0 * opts.refine_tol * curve.isc;
+        for w in curve.v.windows(2).zip(curve.i.windows(2)) {
+            let (v, i) = w;
+            let vm = 0.5 * (v[0] + v[1]);
+            let im = string.i_from_v(&states, vm);
+            assert!((im - 0.5 * (i[0] + i[1])).abs() < tol, "v={vm}: {im} vs {}", 0.5 * (i[0] + i[1]));
+        }
+        assert!(curve.mpp.p > 0.0 && curve.fill_factor() < ff);
+    }
+
+
+    #[test]
+    fn per_module_conditions(){
+        let pnl = PvCell::new(&PARAMS);
+        let string = Series::new(vec![pnl.clone(); 4]);
+        let mut shaded = string.clone();
+        shaded.elements[1].shading = 0.4;
+        shaded.elements[3].shading = 0.5;
+
+        let map = [0.0, 0.4, 0.0, 0.5];
+        assert_eq!(string.states_shading(800.0, 35.0, &map), shaded.states_uniform_conditions(800.0, 35.0));
+        let irrad: Vec<f64> = map.iter().map(|s| 800.0 * (1.0 - s)).collect();
+        let temps = [35.0, 30.0, 35.0, 28.0];
+        let states = string.states_conditions(&irrad, &temps);
+        assert_eq!(states[3], pnl.compute_state(400.0, 28.0));
+
+        let array = Parallel::new(vec![string.clone(), string]);
+        let states = array.states_conditions(&[irrad.clone(), vec![800.0; 4]], &[temps.to_vec(), vec![35.0; 4]]);
+        assert_eq!(states[0], array.elements[0].states_conditions(&irrad, &temps));
+        assert_eq!(array.states_string_conditions(&[800.0, 100.0], &[35.0, 20.0])[1], array.elements[1].states_uniform_conditions(100.0, 20.0));
+        let i_shaded = array.i_from_v(&array.states_shading(800.0, 35.0, &[map.to_vec(), vec![0.0; 4]]), 100.0);
+        assert!(i_shaded < array.i_from_v(&array.states_uniform_conditions(800.0, 35.0), 100.0));
+    }
+
+
+    #[test]
+    fn datasheet_fit(){
+        // datasheet sintético gerado pelo próprio modelo
+        let pnl = PvCell::new(&PARAMS).with_solver(PvCellSolver { method: PvCellMethod::LambertW, ..PvCellSolver::default() });
+        let stc = pnl.compute_state(1000.0, 25.0);
+        let mpp = pnl.try_mpp(&stc).unwrap();
+        let voc = pnl.try_v_from_i(&stc, 0.0).unwrap();
+        let voc_35 = pnl.try_v_from_i(&pnl.compute_state(1000.0, 35.0), 0.0).unwrap();
+        let ds = Datasheet {
+            isc: pnl.try_solve_i(&stc, 0.0).unwrap(), voc, imp: mpp.i, vmp: mpp.v,
+            alpha_sc: PARAMS.alpha_sc, beta_voc: (voc_35 - voc) / 10.0, cells_in_series: 72,
+        };
+        let fit = fit_datasheet(&ds).unwrap();
+        let p = &fit.params;
+        assert!((p.a_ref - PARAMS.a_ref).abs() < 1e-3 * PARAMS.a_ref, "{p:?}");
+        assert!((p.i_o_ref / PARAMS.i_o_ref - 1.0).abs() < 2e-2, "{p:?}");
+        assert!((p.r_s - PARAMS.r_s).abs() < 1e-2 && (p.r_sh_ref / PARAMS.r_sh_ref - 1.0).abs() < 2e-2, "{p:?}");
+        assert!(fit.residuals.iter().all(|r| r.abs() < 1e-5), "{:?}", fit.residuals);
+
+        // datasheet comercial (60 células)
+        let ds = Datasheet { isc: 9.43, voc: 38.3, imp: 8.91, vmp: 31.4, alpha_sc: 0.0047, beta_voc: -0.119, cells_in_series: 60 };
+        let cell = PvCell::from_datasheet(&ds).unwrap().with_solver(PvCellSolver { tol_i: 1e-9, tol_v: 1e-9, ..PvCellSolver::default() });
+        let stc = cell.compute_state(1000.0, 25.0);
+        assert!((cell.solve_i(&stc, 31.4) - 8.91).abs() < 1e-6);
+        assert!((cell.v_from_i(&stc, 0.0) - 38.3).abs() < 1e-6);
+
+        let bad = Datasheet { imp: 9.5, ..ds.clone() };
+        assert!(matches!(fit_datasheet(&bad), Err(FitError::InvalidInput(_))));
+        let bad = Datasheet { vmp: 36.5, imp: 9.3, ..ds };
+        assert!(fit_datasheet(&bad).is_err());
+    }
+
+    #[test]
+    fn iv_curve_fit(){
+        // curva medida sintética: 2 módulos em série, 700 W/m², 45 °C, ruído determinístico de ±5 mA
+        let (irrad, temp) = (700.0, 45.0);
+        let truth = PvCell::new(&PARAMS).with_ns(2);
+        let state = truth.compute_state(irrad, temp);
+        let voc = truth.v_from_i(&state, 0.0);
+        let mut seed: u64 = 12345;
+        let points: Vec<(f64, f64)> = (0..=60).map(|k| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let noise: f64 = ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 0.01;
+            let v: f64 = voc * k as f64 / 60.0;
+            (v, truth.solve_i(&state, v) + noise)
+        }).collect();
+
+        let template = PvCell::new(&BasicParams { a_ref: 2.2, r_s: 0.3, r_sh_ref: 300.0, i_o_ref: 1e-9, ..PARAMS }).with_ns(2);
+        let fit = fit_iv_curve(&points, irrad, temp, &template).unwrap();
+        let p = &fit.params;
+        assert!(fit.r_squared > 0.9999 && fit.rmse < 5e-3, "{} {}", fit.r_squared, fit.rmse);
+        assert!((p.il.value - state.il).abs() < 1e-2 && p.il.low < state.il && state.il < p.il.high, "{p:?}");
+        assert!((p.a.value * state.ra - 1.0).abs() < 5e-2 && p.a.low < p.a.value && p.a.value < p.a.high, "{p:?}");
+        assert!((p.r_s.value - PARAMS.r_s).abs() < 0.1, "{p:?}");
+
+        // o estado reconstruído nas condições de medição é o ajustado
+        let back = fit.cell.compute_state(irrad, temp);
+        assert!((back.i0 / fit.state.i0 - 1.0).abs() < 1e-9 && (back.il / fit.state.il - 1.0).abs() < 1e-9);
+        assert!((back.ra / fit.state.ra - 1.0).abs() < 1e-9 && (back.gsh / fit.state.gsh - 1.0).abs() < 1e-9);
+        assert!((fit.cell.solve_i(&back, 0.8 * voc) - truth.solve_i(&state, 0.8 * voc)).abs() < 2e-2);
+
+        assert!(matches!(fit_iv_curve(&points[..5], irrad, temp, &template), Err(FitError::InvalidInput(_))));
+        assert!(matches!(fit_iv_curve(&points, irrad, temp, &PvCell::default()), Err(FitError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn two_diode(){
+        let solver = PvCellSolver { tol_i: 1e-9, tol_v: 1e-9, ..PvCellSolver::default() };
+        let one = PvCell::new(&PARAMS).with_solver(solver.clone());
+        let two = one.clone().with_recombination_diode(2e-6, 2.0 * PARAMS.a_ref);
+
+        // sem diodo de recombinação o modelo é o de um diodo
+        let s1 = one.compute_state(1000.0, 25.0);
+        assert_eq!((s1.i02, s1.ra2), (0.0, 0.0));
+        assert_eq!(one.clone().with_recombination_diode(0.0, 3.0).solve_i(&one.compute_state(1000.0, 25.0), 40.0), one.solve_i(&s1, 40.0));
+
+        // a equação de dois diodos é satisfeita, e LambertW recorre a Newton
+        let s2 = two.compute_state(1000.0, 40.0);
+        let v: f64 = 38.0;
+        let i: f64 = two.try_solve_i(&s2, v).unwrap();
+        let vj: f64 = v + i * two.r_s;
+        let res: f64 = s2.il - i - s2.i0 * (vj * s2.ra).exp_m1() - s2.i02 * (vj * s2.ra2).exp_m1() - vj * s2.gsh;
+        assert!(res.abs() < 1e-8, "{res}");
+        let lw = two.clone().with_solver(PvCellSolver { method: PvCellMethod::LambertW, ..solver });
+        assert_eq!(lw.try_solve_i(&s2, v).unwrap(), i);
+        assert!((two.try_v_from_i(&s2, i).unwrap() - v).abs() < 1e-6);
+
+        // a perda relativa de eficiência em baixa irradiância é maior com o segundo diodo
+        let eff = |c: &PvCell, g: f64| c.try_mpp(&c.compute_state(g, 25.0)).unwrap().p / g;
+        assert!(eff(&two, 200.0) / eff(&two, 1000.0) < eff(&one, 200.0) / eff(&one, 1000.0));
+
+        // inversa de compute_state preserva o segundo diodo
+        let back = two.clone().with_state(&s2, two.r_s, 1000.0, 40.0);
+        assert!((back.i_o2_ref / two.i_o2_ref - 1.0).abs() < 1e-12 && (back.a2_ref / two.a2_ref - 1.0).abs() < 1e-12);
+
+        // plugável em Series e Parallel
+        let series = Series::new(vec![two.clone(), two.clone(), one.clone()]);
+        let states = series.states_uniform_conditions(800.0, 30.0);
+        let i_str: f64 = series.try_i_from_v(&states, 80.0).unwrap();
+        assert!((series.try_v_from_i(&states, i_str).unwrap() - 80.0).abs() < 0.05);
+        assert_eq!(series.reduce().0.len(), 2);
+        let array = Parallel::new(vec![series.clone(), series]);
+        assert!(array.try_mpp(&array.states_uniform_conditions(800.0, 30.0)).unwrap().p > 0.0);
+    }
+
+    #[test]
+    fn reverse_breakdown(){
+        let br = Breakdown { v_br: -15.0, m: 3.7, a: 0.1 };
+        let solver = PvCellSolver { tol_i: 1e-9, tol_v: 1e-9, ..PvCellSolver::default() };
+        let plain = PvCell::new(&PARAMS).with_solver(solver).without_bypass();
+        let cell = plain.clone().with_breakdown(br);
+        let state = cell.compute_state(1000.0, 25.0);
+
+        // em polarização direta o termo de avalanche é desprezível
+        assert!((cell.try_solve_i(&state, 30.0).unwrap() - plain.try_solve_i(&state, 30.0).unwrap()).abs() < 1e-3);
+
+        // a corrente cresce sem limite perto de v_br, e v_from_i inverte solve_i
+        let i_10: f64 = cell.try_solve_i(&state, -10.0).unwrap();
+        let i_14: f64 = cell.try_solve_i(&state, -14.0).unwrap();
+        assert!(i_14 > i_10 && i_10 > plain.try_solve_i(&state, -10.0).unwrap());
+        assert!((cell.try_v_from_i(&state, i_14).unwrap() + 14.0).abs() < 1e-6);
+        let v: f64 = cell.try_v_from_i(&state, 50.0).unwrap();
+        assert!(v > -15.0 - 50.0 * PARAMS.r_s && v < -14.0, "{v}");
+
+        // módulo sombreado forçado à corrente da string: dissipação finita, limitada pela ruptura
+        let shaded = cell.clone().with_shading(0.8);
+        let series = Series::new(vec![cell.clone(), cell.clone(), shaded.clone()]);
+        let states = series.states_uniform_conditions(1000.0, 25.0);
+        let vs = series.try_vs_from_i(&states, 7.0).unwrap();
+        let p_hot: f64 = -vs[2] * 7.0;
+        assert!(vs[2] < 0.0 && vs[2] > -15.0 - 7.0 * PARAMS.r_s && p_hot > 0.0 && p_hot < 7.0 * 15.0 + 49.0 * PARAMS.r_s, "{vs:?}");
+
+        // com bypass o mesmo módulo fica limitado à tensão do diodo
+        let bypassed = PvCell { v_bypass: -0.65 * 3.0, ..shaded };
+        assert!(bypassed.try_v_from_i(&bypassed.compute_state(1000.0, 25.0), 7.0).unwrap() > -3.0);
+    }
+
+    #[test]
+    fn cell_level_module(){
+        let solver = PvCellSolver { tol_i: 1e-9, tol_v: 1e-9, ..PvCellSolver::default() };
+        let pnl = PvCell::new(&PARAMS).with_solver(solver);
+        let cell = pnl.split_series(72);
+        let module = Module::standard(&cell, 72, 3, BypassDiode::default());
+        assert_eq!((module.len(), module.n_cells()), (3, 72));
+
+        // condições uniformes: equivale ao PvCell do módulo inteiro
+        let states = module.states_uniform_conditions(1000.0, 25.0);
+        let stc = pnl.compute_state(1000.0, 25.0);
+        assert!((module.try_v_from_i(&states, 0.0).unwrap() - pnl.try_v_from_i(&stc, 0.0).unwrap()).abs() < 1e-2);
+        let mpp = module.try_mpp(&states).unwrap();
+        assert!((mpp.p / pnl.try_mpp(&stc).unwrap().p - 1.0).abs() < 1e-3, "{mpp:?}");
+        let i: f64 = module.try_i_from_v(&states, 30.0).unwrap();
+        assert!((i - pnl.try_solve_i(&stc, 30.0).unwrap()).abs() < 1e-3);
+
+        // uma célula sombreada aciona exatamente um bypass
+        let mut shading: Vec<f64> = vec![0.0; 72];
+        shading[30] = 0.8;
+        let states = module.states_shading(1000.0, 25.0, &shading);
+        let vs = module.try_vs_from_i(&states, 7.0).unwrap();
+        let i_bp = module.bypass_currents(&states, &vs);
+        assert!(i_bp[1] > 5.0 && vs[1] < -0.3 && vs[1] > -0.6, "{vs:?} {i_bp:?}");
+        assert!(i_bp[0].abs() < 1e-3 && i_bp[2].abs() < 1e-3, "{i_bp:?}");
+        let mpp_shaded = module.try_mpp(&states).unwrap();
+        assert!(mpp_shaded.p < mpp.p && mpp_shaded.p > 0.6 * mpp.p, "{mpp_shaded:?}");
+
+        // o diodo de bypass segue a temperatura das células: queda direta menor a 65 °C
+        let hot = module.states_shading(1000.0, 65.0, &shading);
+        assert_eq!(hot[1].bypass, BypassDiode::default().at_temperature(65.0));
+        let vs_hot = module.try_vs_from_i(&hot, 7.0).unwrap();
+        assert!(vs_hot[1] > vs[1] + 0.03, "{vs_hot:?} vs {vs:?}");
+        let uniform = module.states_conditions(&[1000.0; 72], &[65.0; 72]);
+        assert_eq!(uniform, module.states_uniform_conditions(1000.0, 65.0));
+
+        // meia célula: 144 meias células, duas metades em paralelo em cada substring
+        let half = Module::half_cut(&cell, 144, 3, BypassDiode::default());
+        assert_eq!((half.len(), half.n_cells(), half.substrings[0].cells.len()), (3, 144, 2));
+        let states = half.states_uniform_conditions(1000.0, 25.0);
+        assert!((half.try_v_from_i(&states, 7.0).unwrap() - module.try_v_from_i(&module.states_uniform_conditions(1000.0, 25.0), 7.0).unwrap()).abs() < 1e-2);
+        let mut shading: Vec<f64> = vec![0.0; 144];
+        shading[100] = 0.9;
+        let states = half.states_shading(1000.0, 25.0, &shading);
+        let i_bp = half.bypass_currents(&states, &half.try_vs_from_i(&states, 7.0).unwrap());
+        assert_eq!(i_bp.iter().filter(|&&i| i > 1.0).count(), 1, "{i_bp:?}");
+        // abaixo da corrente da metade sã o bypass não conduz
+        let i_bp = half.bypass_currents(&states, &half.try_vs_from_i(&states, 3.0).unwrap());
+        assert!(i_bp.iter().all(|&i| i < 1e-2), "{i_bp:?}");
+    }
+
+    #[test]
+    fn shockley_bypass(){
+        let diode = BypassDiode::default();
+        let solver = PvCellSolver { tol_i: 1e-9, tol_v: 1e-9, ..PvCellSolver::default() };
+        let pnl = PvCell::new(&PARAMS).with_solver(solver).with_bypass_diode(diode);
+        let state = pnl.compute_state(1000.0, 25.0);
+        assert_eq!(state.bypass, Some(diode));
+
+        // I(V) contínua e estritamente decrescente através da ativação do bypass, e v_from_i a inverte
+        let vs: Vec<f64> = (0..=100).map(|k| -2.0 + 0.05 * k as f64).collect();
+        let is: Vec<f64> = vs.iter().map(|&v| pnl.try_solve_i(&state, v).unwrap()).collect();
+        assert!(is.windows(2).all(|w| w[1] < w[0]));
+        let slopes: Vec<f64> = is.windows(2).map(|w| (w[1] - w[0]) / 0.05).collect();
+        assert!(slopes.windows(2).all(|s| s[1] >= s[0] - 1e-9), "condutância monotona: {slopes:?}");
+        for (&v, &i) in vs.iter().zip(&is) {
+            assert!((pnl.try_v_from_i(&state, i).unwrap() - v).abs() < 1e-6, "{v} {i}");
+        }
+        assert!(pnl.try_v_from_i(&state, 10.0).unwrap() > -0.6);
+
+        // diodo mais quente conduz com menor tensão
+        let hot = pnl.compute_state(1000.0, 75.0);
+        assert!(pnl.try_v_from_i(&hot, 10.0).unwrap() > pnl.try_v_from_i(&state, 10.0).unwrap());
+
+        // string com um módulo sombreado: o bypass assume a corrente sem descontinuidade
+        let series = Series::new(vec![pnl.clone(), pnl.clone(), pnl.clone().with_shading(0.7)]);
+        let states = series.states_uniform_conditions(1000.0, 25.0);
+        let mut stats = SolverStats::new();
+        let i: f64 = series.try_i_from_v_with_stats(&states, 60.0, &mut stats).unwrap();
+        let v_shaded: f64 = series.try_vs_from_i(&states, i).unwrap()[2];
+        assert!(i > 5.0 && v_shaded < 0.0 && v_shaded > -0.6, "{i} {v_shaded}");
+        assert_eq!(stats.series.non_converged, 0);
+    }
+
+    #[test]
+    fn series_brent(){
+        // string de células com uma célula sombreada: curva rígida
+        let cell = PvCell::new(&PARAMS).with_solver(PvCellSolver { tol_i: 1e-9, tol_v: 1e-9, ..PvCellSolver::default() }).split_series(72);
+        let brent = Series::new(vec![cell; 24]).with_solver(SeriesSolver { tol_v: 1e-4, method: SeriesMethod::Brent, ..SeriesSolver::default() });
+        let mut shading: Vec<f64> = vec![0.0; 24];
+        shading[5] = 0.9;
+        let states = brent.states_shading(1000.0, 25.0, &shading);
+
+        let mut stats = SolverStats::new();
+        for v in [-5.0, 0.0, 5.0, 12.0, 15.0, 16.0] {
+            let i: f64 = brent.try_i_from_v_with_stats(&states, v, &mut stats).unwrap();
+            assert!((brent.try_v_from_i(&states, i).unwrap() - v).abs() < 1e-4, "{v} {i}");
+        }
+        assert_eq!(stats.series.non_converged, 0);
+        assert!(stats.series.iterations < 6 * 60, "{:?}", stats.series);
+
+        // curvas suaves: mesmo resultado que o método de ganho
+        let string = Series::new(vec![PvCell::new(&PARAMS); 4]);
+        let states = string.states_uniform_conditions(800.0, 40.0);
+        let fast = string.clone().with_solver(SeriesSolver { method: SeriesMethod::Brent, ..SeriesSolver::default() });
+        assert!((fast.try_i_from_v(&states, 150.0).unwrap() - string.try_i_from_v(&states, 150.0).unwrap()).abs() < 1e-2);
+    }
+
+    #[test]
+    fn small_signal_derivatives(){
+        let solver = PvCellSolver { tol_i: 1e-10, tol_v: 1e-10, ..PvCellSolver::default() };
+        let pnl = PvCell::new(&PARAMS).with_solver(solver.clone()).with_ns(2);
+        let fd = |f: &dyn Fn(f64) -> f64, v: f64, h: f64| (f(v + h) - f(v - h)) / (2.0 * h);
+        let close = |a: f64, b: f64, tol: f64| (a - b).abs() <= tol * b.abs().max(1e-3);
+
+        // célula: direta, bypass linear, bypass de Shockley, dois diodos e ruptura
+        let cells = [
+            pnl.clone(),
+            pnl.clone().with_bypass_diode(BypassDiode::default()),
+            pnl.clone().with_recombination_diode(2e-6, 2.0 * PARAMS.a_ref),
+            pnl.clone().without_bypass().with_breakdown(Breakdown { v_br: -15.0, m: 3.7, a: 0.1 }),
+        ];
+        for cell in cells.iter() {
+            let state = cell.compute_state(900.0, 35.0);
+            for v in [-8.0, -4.5, -0.5, 0.0, 60.0, 90.0, 95.0] {
+                let ss = cell.try_small_signal(&state, v).unwrap();
+                let g_fd: f64 = fd(&|x| cell.solve_i(&state, x), v, 1e-5);
+                assert!(ss.g < 0.0 && close(ss.g, g_fd, 1e-4), "{v}: {} vs {}", ss.g, g_fd);
+            }
+        }
+
+        // equivalentes de Norton e Thévenin reproduzem o ponto de operação
+        let state = pnl.compute_state(900.0, 35.0);
+        let ss = pnl.try_small_signal(&state, 80.0).unwrap();
+        let (i_n, g_n) = ss.norton();
+        let (v_th, r_th) = ss.thevenin();
+        assert!((i_n - g_n * ss.v - ss.i).abs() < 1e-9 && (v_th - r_th * ss.i - ss.v).abs() < 1e-9);
+        assert!((ss.r() * ss.g - 1.0).abs() < 1e-12);
+
+        // string com um módulo sombreado (bypass ativo) e arranjo com duas strings
+        let string = Series::new(vec![pnl.clone(), pnl.clone(), pnl.clone().with_shading(0.6)])
+            .with_solver(SeriesSolver { tol_v: 1e-9, method: SeriesMethod::Brent, ..SeriesSolver::default() });
+        let states = string.states_uniform_conditions(900.0, 35.0);
+        for v in [100.0, 180.0, 250.0] {
+            let ss = string.try_small_signal(&states, v).unwrap();
+            let g_fd: f64 = fd(&|x| string.i_from_v(&states, x), v, 1e-3);
+            assert!(close(ss.g, g_fd, 1e-3), "{v}: {} vs {}", ss.g, g_fd);
+        }
+        let array = Parallel::new(vec![string.clone(), Series::new(vec![pnl.clone(); 3]).with_solver(string.solver.clone())])
+            .with_solver(ParallelSolver { tol_i: 1e-9, ..ParallelSolver::default() });
+        let states = array.states_uniform_conditions(900.0, 35.0);
+        let ss = array.try_small_signal(&states, 200.0).unwrap();
+        let g_fd: f64 = fd(&|x| array.i_from_v(&states, x), 200.0, 1e-3);
+        assert!(close(ss.g, g_fd, 1e-3), "{} vs {}", ss.g, g_fd);
+
+        // módulo célula a célula com um bypass ativo
+        let module = Module::standard(&PvCell::new(&PARAMS).with_solver(solver).split_series(72), 72, 3, BypassDiode::default())
+            .with_solver(ModuleSolver { tol_v: 1e-9, ..ModuleSolver::default() });
+        let mut shading: Vec<f64> = vec![0.0; 72];
+        shading[3] = 0.8;
+        let states = module.states_shading(900.0, 35.0, &shading);
+        let ss = module.try_small_signal(&states, 25.0).unwrap();
+        let g_fd: f64 = fd(&|x| module.i_from_v(&states, x), 25.0, 1e-3);
+        assert!(close(ss.g, g_fd, 1e-3), "{} vs {}", ss.g, g_fd);
+
+        // passos de Newton no nível da string
+        let newton = string.clone().with_solver(SeriesSolver { method: SeriesMethod::Newton, ..string.solver.clone() });
+        let states = string.states_uniform_conditions(900.0, 35.0);
+        let (mut s_brent, mut s_newton) = (SolverStats::new(), SolverStats::new());
+        for v in [20.0, 100.0, 180.0, 250.0] {
+            let i_b: f64 = string.try_i_from_v_with_stats(&states, v, &mut s_brent).unwrap();
+            let i_n: f64 = newton.try_i_from_v_with_stats(&states, v, &mut s_newton).unwrap();
+            assert!((i_b - i_n).abs() < 1e-6, "{v}: {i_b} {i_n}");
+        }
+        assert!(s_newton.series.iterations <= s_brent.series.iterations, "{:?} {:?}", s_newton.series, s_brent.series);
+    }
+
+
+    #[test]
+    fn nested_topologies(){
+        let pnl = PvCell::new(&PARAMS).with_solver(PvCellSolver { tol_i: 1e-9, tol_v: 1e-9, ..PvCellSolver::default() });
+        let fine = SeriesSolver { tol_v: 1e-6, method: SeriesMethod::Brent, ..SeriesSolver::default() };
+        let fine_p = ParallelSolver { tol_i: 1e-6, ..ParallelSolver::default() };
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-4 * b.abs().max(1.0);
+
+        // sem v_oc_ref (PvCell::default): Voc nominal estimado pelos parâmetros básicos
+        let no_voc = PvCell { v_oc_ref: f64::NAN, ..pnl.clone() };
+        let stc = no_voc.compute_state(1000.0, 25.0);
+        assert!((no_voc.nominal_voc() - no_voc.try_v_from_i(&stc, 0.0).unwrap()).abs() < 0.5, "{}", no_voc.nominal_voc());
+        let string = Series::new(vec![no_voc; 3]).with_solver(fine.clone());
+        let i: f64 = string.try_i_from_v(&string.states_uniform_conditions(1000.0, 25.0), 120.0).unwrap();
+        assert!(close(i, pnl.try_solve_i(&stc, 40.0).unwrap()), "{i}");
+        // sem parâmetros: erro, não pânico
+        let empty = PvCell::default();
+        let state = empty.compute_state(1000.0, 25.0);
+        assert!(empty.try_v_from_i(&state, 1.0).is_err() && !empty.v_from_i(&state, 1.0).is_finite());
+        assert!(empty.nominal_voc().is_nan());
+
+        // bloco de dois módulos em paralelo dentro de uma string
+        let one = Series::new(vec![pnl.clone()]).with_solver(fine.clone());
+        let single = Parallel::new(vec![one.clone()]).with_solver(fine_p.clone());
+        let pair = Parallel::new(vec![one; 2]).with_solver(fine_p.clone());
+        let nested: Series<Parallel> = Series::new(vec![single.clone(), single, pair]).with_solver(fine.clone());
+        let flat = Series::new(vec![pnl.clone(), pnl.clone(), pnl.clone().with_np(2)]).with_solver(fine.clone());
+        let states_n = nested.states_uniform_conditions(800.0, 40.0);
+        let states_f = flat.states_uniform_conditions(800.0, 40.0);
+        for v in [0.0, 60.0, 120.0] {
+            let (i_n, i_f) = (nested.try_i_from_v(&states_n, v).unwrap(), flat.try_i_from_v(&states_f, v).unwrap());
+            assert!(close(i_n, i_f), "{v}: {i_n} vs {i_f}");
+        }
+
+        // arranjos em série: equivalem a um arranjo com strings duas vezes mais longas
+        let array = Parallel::new(vec![Series::new(vec![pnl.clone(); 5]).with_solver(fine.clone()); 2]).with_solver(fine_p.clone());
+        let chain: Series<Parallel> = Series::new(vec![array; 2]).with_solver(fine.clone());
+        let long = Parallel::new(vec![Series::new(vec![pnl.clone(); 10]).with_solver(fine.clone()); 2]).with_solver(fine_p.clone());
+        let mpp_c = chain.try_mpp(&chain.states_uniform_conditions(800.0, 40.0)).unwrap();
+        let mpp_l = long.try_mpp(&long.states_uniform_conditions(800.0, 40.0)).unwrap();
+        assert!(close(mpp_c.p, mpp_l.p), "{mpp_c:?} vs {mpp_l:?}");
+
+        // a redução atravessa os níveis: um único PvCell com ns = 10 e np = 2
+        let reduced = chain.reduced();
+        let cell = &reduced[0].elements[0][0];
+        assert_eq!((reduced.len(), reduced[0].len(), reduced[0].elements[0].len(), cell.ns, cell.np), (1, 1, 1, 10, 2));
+        let mpp_r = reduced.try_mpp(&reduced.states_uniform_conditions(800.0, 40.0)).unwrap();
+        assert!(close(mpp_r.p, mpp_l.p), "{mpp_r:?} vs {mpp_l:?}");
+
+        // string de módulos modelados célula a célula
+        let module = Module::standard(&pnl.split_series(72), 72, 3, BypassDiode::default());
+        let modules: Series<Module> = Series::new(vec![module.clone(); 2]).with_solver(fine);
+        let states_m = module.states_uniform_conditions(800.0, 40.0);
+        let i_s: f64 = modules.try_i_from_v(&modules.states_uniform_conditions(800.0, 40.0), 60.0).unwrap();
+        assert!((i_s - module.try_i_from_v(&states_m, 30.0).unwrap()).abs() < 1e-3);
+    }
+
+
+    #[test]
+    fn netlist_solver(){
+        let pnl = PvCell::new(&PARAMS).with_solver(PvCellSolver { tol_i: 1e-9, tol_v: 1e-9, ..PvCellSolver::default() });
+        let fine = SeriesSolver { tol_v: 1e-7, method: SeriesMethod::Brent, ..SeriesSolver::default() };
+        let fine_p = ParallelSolver { tol_i: 1e-7, ..ParallelSolver::default() };
+
+        // string de três módulos (um sombreado) com uma fonte de tensão nos terminais
+        let mut net = Netlist::new();
+        let nodes: Vec<NodeId> = (0..3).map(|_| net.node()).collect();
+        net.add_cell(pnl.clone(), nodes[0], GROUND);
+        net.add_cell(pnl.clone(), nodes[1], nodes[0]);
+        net.add_cell(pnl.clone().with_shading(0.5), nodes[2], nodes[1]);
+        let src = net.add_voltage_source(0.0, nodes[2], GROUND);
+        let string = Series::new(vec![pnl.clone(), pnl.clone(), pnl.clone().with_shading(0.5)]).with_solver(fine.clone());
+        let states = net.states_uniform_conditions(800.0, 40.0);
+        let voltages = [0.0, 20.0, 80.0, 120.0];
+        for (v, sol) in voltages.iter().zip(net.try_sweep(&states, src, &voltages).unwrap()) {
+            let i: f64 = string.try_i_from_v(&states.cells, *v).unwrap();
+            assert!((sol.currents[src] + i).abs() < 1e-5, "{v}: {} vs {i}", -sol.currents[src]);
+            assert!((sol.power(0) + sol.power(1) + sol.power(2) + sol.power(src)).abs() < 1e-4, "{sol:?}");
+        }
+
+        // carga resistiva: o ponto de operação está na curva da string
+        let mut load = net.clone();
+        load.components.pop();
+        let r = load.add_resistor(10.0, nodes[2], GROUND);
+        let sol = load.try_solve(&states).unwrap();
+        let v: f64 = sol.voltages[nodes[2]];
+        assert!((sol.currents[r] - string.try_i_from_v(&states.cells, v).unwrap()).abs() < 1e-5 && (v - 10.0 * sol.currents[r]).abs() < 1e-9);
+
+        // duas strings com interligações em cada nível: equivale a blocos paralelos em série
+        let mut tied = Netlist::new();
+        let levels: Vec<NodeId> = (0..3).map(|_| tied.node()).collect();
+        for (k, &top) in levels.iter().enumerate() {
+            let bottom: NodeId = if k == 0 { GROUND } else { levels[k - 1] };
+            tied.add_cell(pnl.clone(), top, bottom);
+            tied.add_cell(if k == 1 { pnl.clone().with_shading(0.7) } else { pnl.clone() }, top, bottom);
+        }
+        let src = tied.add_voltage_source(0.0, levels[2], GROUND);
+        let block = |shaded: bool| Parallel::new(vec![
+            Series::new(vec![pnl.clone()]).with_solver(fine.clone()),
+            Series::new(vec![if shaded { pnl.clone().with_shading(0.7) } else { pnl.clone() }]).with_solver(fine.clone()),
+        ]).with_solver(fine_p.clone());
+        let nested: Series<Parallel> = Series::new(vec![block(false), block(true), block(false)]).with_solver(fine);
+        let states = tied.states_uniform_conditions(800.0, 40.0);
+        let states_n = nested.states_uniform_conditions(800.0, 40.0);
+        for (v, sol) in voltages.iter().zip(tied.try_sweep(&states, src, &voltages).unwrap()) {
+            let i: f64 = nested.try_i_from_v(&states_n, *v).unwrap();
+            assert!((sol.currents[src] + i).abs() < 1e-4, "{v}: {} vs {i}", -sol.currents[src]);
+        }
+
+        // fonte de corrente em um diodo
+        let mut diode_net = Netlist::new();
+        let n = diode_net.node();
+        diode_net.add_current_source(5.0, n, GROUND);
+        let d = diode_net.add_diode(BypassDiode::default(), n, GROUND);
+        let sol = diode_net.try_solve(&diode_net.states_uniform_conditions(0.0, 25.0)).unwrap();
+        assert!((sol.voltages[n] - BypassDiode::default().v_from_i(5.0)).abs() < 1e-6 && (sol.currents[d] - 5.0).abs() < 1e-6);
+        // diodo quente: menor tensão direta
+        assert_eq!(diode_net.states_conditions(&[], &[]), diode_net.states_uniform_conditions(0.0, 25.0));
+        let sol = diode_net.try_solve(&diode_net.state(Vec::new(), 70.0)).unwrap();
+        assert!((sol.voltages[n] - BypassDiode::default().at_temperature(70.0).v_from_i(5.0)).abs() < 1e-6);
+        assert!(sol.voltages[n] < BypassDiode::default().v_from_i(5.0) - 0.05, "{}", sol.voltages[n]);
+
+        // modo estrito: uma célula que não converge faz o try_solve falhar
+        let mut starved = load.clone();
+        for c in starved.components.iter_mut() {
+            if let Component::Cell { cell, .. } = c {
+                cell.solver.max_iter = 1;
+            }
+        }
+        let states = starved.states_uniform_conditions(800.0, 40.0);
+        assert!(matches!(starved.try_solve(&states), Err(SolverError::NotConverged { input: OperatingPoint::Voltage(_), .. })));
+        assert!(starved.solve(&states).currents.iter().all(|i| i.is_finite()));
+    }
+
+
+    #[test]
+    fn spice_export(){
+        let pnl = PvCell::new(&PARAMS).with_ns(2).with_np(3).with_solver(PvCellSolver { tol_i: 1e-12, tol_v: 1e-12, ..PvCellSolver::default() });
+        let state = pnl.compute_state(800.0, 40.0);
+        let net: String = pnl.to_spice(&state, "cell");
+        let field = |prefix: &str, k: usize| -> f64 {
+            let line = net.lines().find(|l| l.starts_with(prefix)).unwrap();
+            line.split([' ', '=', ')']).filter(|t| !t.is_empty()).nth(k).unwrap().parse().unwrap()
+        };
+        let (il, rsh, rs) = (field("IL ", 4), field("RSH ", 3), field("RS ", 3));
+        let (is, n) = (field(".MODEL cell_d1 ", 4), field(".MODEL cell_d1 ", 6));
+        let vt: f64 = 298.15 * 1.38064852e-23 / 1.60217663e-19;
+
+        // o circuito exportado satisfaz a equação do PvCell nos terminais
+        for v in [0.0, 40.0, 80.0, 95.0] {
+            let i: f64 = pnl.try_solve_i(&state, v).unwrap();
+            let vj: f64 = v + i * rs;
+            let i_spice: f64 = il - is * ((vj / (n * vt)).exp() - 1.0) - vj / rsh;
+            assert!((i_spice - i).abs() < 1e-6 * il, "{v}: {i_spice} vs {i}");
+        }
+        assert!(net.contains("VBP b c DC") && net.starts_with(".SUBCKT cell pos neg") && net.trim_end().ends_with(".ENDS cell"));
+
+        // hierarquia: uma subcircuito por elemento e nós internos encadeados
+        let string = Series::new(vec![pnl.clone(), pnl.clone().with_breakdown(Breakdown { v_br: -15.0, m: 3.7, a: 0.1 })]);
+        let array = Parallel::new(vec![string.clone(); 2]);
+        let net = array.to_spice(&array.states_uniform_conditions(800.0, 40.0), "arr");
+        assert_eq!(net.matches(".SUBCKT").count(), 7);
+        assert_eq!(net.matches(".SUBCKT").count(), net.matches(".ENDS").count());
+        assert!(net.contains("X0 n1 neg arr_0_0") && net.contains("X1 pos n1 arr_0_1") && net.contains("X1 pos neg arr_1"));
+        assert!(net.contains("BSH j neg I=") && net.contains("pow(1-V(j,neg)/(-3e1),-3.7e0)"));
+
+        // módulo célula a célula: um diodo de bypass por substring
+        let module = Module::standard(&pnl.with_ns(1).with_np(1).split_series(72), 72, 3, BypassDiode::default());
+        let net = module.to_spice(&module.states_uniform_conditions(800.0, 40.0), "mod");
+        assert_eq!(net.matches("\nDBP").count(), 3);
+        assert!(net.contains("DBP0 neg n1 mod_dbp0 TEMP=40\n") && net.contains("X2 pos n2 mod_2"));
+    }
+
+
+    #[test]
+    fn poa_transposition(){
+        let models = [SkyModel::Isotropic, SkyModel::HayDavies, SkyModel::Perez];
+        let (zenith, azimuth): (f64, f64) = (40.0, 160.0);
+        let sky = SkyIrradiance::new(800.0 * 40f64.to_radians().cos() + 100.0, 800.0, 100.0);
+
+        // plano horizontal: todos os modelos devolvem a GHI
+        for model in models {
+            let poa = Transposition::new(model, 0.2).poa(&Surface::new(0.0, 180.0), zenith, azimuth, &sky);
+            assert!((poa.global() - sky.ghi).abs() < 1e-9 && poa.ground == 0.0, "{model:?}: {poa:?}");
+        }
+
+        // plano inclinado para o sol: a anisotropia aumenta a difusa do céu
+        let surface = Surface::new(30.0, 180.0);
+        let poa: Vec<PoaIrradiance> = models.iter().map(|&m| Transposition::new(m, 0.2).poa(&surface, zenith, azimuth, &sky)).collect();
+        assert!(poa[1].sky_diffuse > poa[0].sky_diffuse && poa[2].sky_diffuse > poa[0].sky_diffuse, "{poa:?}");
+        assert!((poa[0].beam - 800.0 * surface.cos_aoi(zenith, azimuth)).abs() < 1e-9 && poa[0].beam == poa[2].beam);
+        assert!((poa[0].ground - sky.ghi * 0.2 * (1.0 - 30f64.to_radians().cos()) / 2.0).abs() < 1e-9);
+        assert!((surface.aoi(30.0, 180.0)).abs() < 1e-6);
+
+        // sol atrás do plano: sem componente direta
+        let north = Surface::new(90.0, 0.0);
+        assert_eq!(Transposition::new(SkyModel::Perez, 0.2).poa(&north, zenith, azimuth, &sky).beam, 0.0);
+
+        // Perez, céu encoberto (primeira faixa de eps), plano vertical voltado para o sol a 60°
+        assert!((relative_airmass(60.0) - 1.99429285).abs() < 1e-7 && relative_airmass(95.0).is_nan());
+        let overcast = SkyIrradiance::new(100.0, 0.0, 200.0);
+        let vertical = Transposition::new(SkyModel::Perez, 0.0).poa(&Surface::new(90.0, 180.0), 60.0, 180.0, &overcast);
+        assert!((vertical.sky_diffuse - 112.104305).abs() < 1e-5, "{vertical:?}");
+
+        // estado a partir das componentes
+        let pnl = PvCell::new(&PARAMS);
+        assert_eq!(pnl.compute_state_poa(&poa[0], 40.0), pnl.compute_state(poa[0].global(), 40.0));
+        let string = Series::new(vec![pnl; 3]);
+        assert_eq!(string.compute_state_poa(&poa[2], 40.0), string.states_uniform_conditions(poa[2].global(), 40.0));
+    }
+
+    #[test]
+    fn solar_position_spa() {
+        // caso de referência do SPA (Reda & Andreas, 2004): 2003-10-17 12:30:30 local, UTC-7
+        let time = UtcTime::new(2003, 10, 17, 19, 30, 30.0);
+        assert!((time.julian_day() - 2452930.312847).abs() < 1e-6);
+        assert_eq!(time.day_of_year(), 290);
+        assert_eq!(UtcTime::from_unix(1066419030.0), time);
+        let site = Site::new(39.742476, -105.1786).with_altitude(1830.14).with_atmosphere(820.0, 11.0);
+        let spa = solar_position(&time, &site, SolarAlgorithm::Spa { delta_t: 67.0 });
+        assert!((spa.zenith - 50.11162).abs() < 1e-5, "zenith {}", spa.zenith);
+        assert!((spa.azimuth - 194.34024).abs() < 1e-5, "azimuth {}", spa.azimuth);
+        assert!((spa.earth_sun_distance - 0.9965422974).abs() < 1e-9);
+        assert!((spa.equation_of_time - 14.64).abs() < 0.05, "eot {}", spa.equation_of_time);
+        assert!((spa.dni_extra - extraterrestrial_irradiance(290)).abs() < 3.0);
+
+        let fast = solar_position(&time, &site, SolarAlgorithm::Fast);
+        assert!((fast.zenith - spa.zenith).abs() < 0.02 && (fast.azimuth - spa.azimuth).abs() < 0.02);
+        assert!((fast.equation_of_time - spa.equation_of_time).abs() < 0.1);
+
+        // alimenta a transposição
+        let sky = SkyIrradiance::new(600.0, 700.0, 150.0).with_dni_extra(spa.dni_extra);
+        let poa = Transposition::new(SkyModel::Perez, 0.2).poa(&Surface::new(30.0, 180.0), spa.zenith, spa.azimuth, &sky);
+        assert!(poa.beam > 0.0 && poa.global() > sky.ghi);
+    }
+
+    #[test]
+    fn thermal_models() {
+        let stc = Ambient::new(25.0, 1.0);
+        assert!((ThermalModel::faiman().cell_temperature(1000.0, &stc) - (25.0 + 1000.0 / 31.84)).abs() < 1e-9);
+        assert!((ThermalModel::ross().cell_temperature(1000.0, &stc) - 45.0).abs() < 1e-9);
+        assert!((ThermalModel::pvsyst_freestanding().cell_temperature(1000.0, &stc) - (25.0 + 810.0 / 29.0)).abs() < 1e-9);
+        let sandia: f64 = ThermalModel::sandia_open_rack().cell_temperature(1000.0, &stc);
+        assert!((sandia - (28.0 + 1000.0 * (-3.635f64).exp())).abs() < 1e-9);
+        assert!((ThermalModel::sandia_open_rack().module_temperature(1000.0, &stc) - (sandia - 3.0)).abs() < 1e-9);
+        assert_eq!(ThermalModel::faiman().module_temperature(1000.0, &stc), ThermalModel::faiman().cell_temperature(1000.0, &stc));
+
+        // condições NOCT: 800 W/m2, 20 °C, 1 m/s
+        let noct = ThermalModel::Noct { noct: 45.0, eta: 0.0 };
+        assert!((noct.cell_temperature(800.0, &Ambient::new(20.0, 1.0)) - 45.0).abs() < 1e-9);
+
+        // o vento esfria, a noite fica na temperatura do ar
+        for model in [ThermalModel::noct(45.0), ThermalModel::sandia_close_roof(), ThermalModel::faiman()] {
+            assert!(model.cell_temperature(800.0, &Ambient::new(20.0, 5.0)) < model.cell_temperature(800.0, &Ambient::new(20.0, 1.0)));
+            assert_eq!(model.cell_temperature(-1.0, &stc), 25.0);
+        }
+
+        // estados por elemento a partir do clima
+        let pnl = PvCell::new(&PARAMS);
+        let thermal = ThermalModel::default();
+        let poa = PoaIrradiance { beam: 700.0, sky_diffuse: 100.0, ground: 0.0 };
+        let t_cell: f64 = thermal.cell_temperature(800.0, &stc);
+        assert_eq!(pnl.compute_state_ambient(&poa, &stc, &thermal), pnl.compute_state(800.0, t_cell));
+        let array = Parallel::new(vec![Series::new(vec![pnl.clone(); 2]); 2]);
+        let irrad: Vec<Vec<f64>> = vec![vec![800.0, 200.0], vec![800.0, 800.0]];
+        let states = array.states_ambient(&irrad, &irrad, &stc, &thermal);
+        assert_eq!(states[0][0], states[1][1]);
+        assert_eq!(states[0][1], pnl.compute_state(200.0, thermal.cell_temperature(200.0, &stc)));
+        // célula sombreada: menos irradiância efetiva, mas a temperatura segue a irradiância POA
+        let poa_global: Vec<Vec<f64>> = vec![vec![800.0; 2]; 2];
+        let states = array.states_ambient(&poa_global, &irrad, &stc, &thermal);
+        assert_eq!(states[0][1], pnl.compute_state(200.0, t_cell));
+    }
+
+    #[test]
+    fn transient_thermal() {
+        let steady = ThermalModel::faiman();
+        let ambient = vec![Ambient::new(25.0, 1.0); 40];
+        // nuvem passageira: 1000 -> 200 W/m2 por 2 min, passo de 10 s
+        let mut poa: Vec<f64> = vec![1000.0; 40];
+        poa[10..22].fill(200.0);
+        let mut thermal = TransientThermal::from_heat_capacity(steady, 11000.0, 31.84);
+        let temps: Vec<f64> = thermal.run(&poa, &ambient, 10.0);
+
+        let (hot, cold): (f64, f64) = (steady.cell_temperature(1000.0, &ambient[0]), steady.cell_temperature(200.0, &ambient[0]));
+        assert_eq!(temps[0], hot);
+        // atraso: não chega ao regime da nuvem e resfria monotonicamente
+        assert!(temps[21] > cold + 1.0 && temps[21] < hot);
+        assert!(temps[10..22].windows(2).all(|w| w[1] < w[0]));
+        // solução exata de primeira ordem
+        let expected: f64 = cold + (hot - cold) * (-120.0 / thermal.tau).exp();
+        assert!((temps[21] - expected).abs() < 1e-9);
+        // passos menores dão a mesma trajetória
+        let mut fine = TransientThermal::new(steady, thermal.tau);
+        let fine_temps: Vec<f64> = fine.run(&poa.iter().flat_map(|&g| [g, g]).collect::<Vec<f64>>(), &[ambient.clone(), ambient.clone()].concat(), 5.0);
+        assert!((fine_temps[43] - temps[21]).abs() < 1e-9);
+
+        // estados por passo
+        let pnl = PvCell::new(&PARAMS);
+        let sky = vec![PoaIrradiance { beam: 800.0, sky_diffuse: 100.0, ground: 0.0 }; 3];
+        let mut thermal = TransientThermal::new(steady, 300.0);
+        thermal.temperature = Some(25.0);
+        let states = thermal.states(&pnl, &sky, &ambient[..3], 60.0);
+        assert_eq!(states.len(), 3);
+        assert!(states[0].i0 < states[2].i0 && thermal.temperature.unwrap() < steady.cell_temperature(900.0, &ambient[0]));
+    }
+
+    #[test]
+    fn optical_losses() {
+        let models = [Iam::ashrae(), Iam::martin_ruiz(), Iam::physical(), Iam::Table(vec![(0.0, 1.0), (60.0, 0.9), (90.0, 0.0)])];
+        for iam in &models {
+            assert!((iam.modifier(0.0) - 1.0).abs() < 1e-12, "{iam:?}");
+            let curve: Vec<f64> = (0..=18).map(|k| iam.modifier(5.0 * k as f64)).collect();
+            assert!(curve.windows(2).all(|w| w[1] <= w[0]) && curve[18] == 0.0, "{iam:?}: {curve:?}");
+        }
+        assert!((Iam::ashrae().modifier(60.0) - 0.95).abs() < 1e-12);
+        assert!((models[3].modifier(75.0) - 0.45).abs() < 1e-12 && (models[3].modifier(-30.0) - 0.95).abs() < 1e-12);
+        // vidro: perdas pequenas até 60°, grandes perto da rasante
+        let glass = Iam::physical();
+        assert!((glass.modifier(60.0) - 0.946003).abs() < 1e-6 && glass.modifier(80.0) < 0.7);
+
+        // plano horizontal: sem componente do solo; difusa do céu a 59.7°
+        let (sky, ground) = glass.diffuse_modifiers(0.0);
+        assert_eq!((sky, ground), (glass.modifier(59.7), 0.0));
+
+        // espectral: próximo de 1 no espectro de referência, sem efeito com o sol abaixo do horizonte
+        let ama: f64 = absolute_airmass(relative_airmass(48.19), 1013.25);
+        assert!((ama - 1.5).abs() < 0.01);
+        let fs = SpectralModel::first_solar_monosi();
+        assert!((fs.modifier(ama, 1.42) - 1.0).abs() < 0.01);
+        // mais vapor d'água absorve o infravermelho: ganho relativo, maior no CdTe
+        let cdte = SpectralModel::first_solar_cdte();
+        let gain = |m: &SpectralModel| m.modifier(ama, 3.0) - m.modifier(ama, 0.5);
+        assert!(gain(&fs) > 0.0 && gain(&cdte) > gain(&fs));
+        assert_eq!(fs.modifier(relative_airmass(95.0), 1.0), 1.0);
+        assert_eq!(SpectralModel::Sapm([1.0, 0.0, 0.0, 0.0, 0.0]).modifier(3.0, 1.0), 1.0);
+
+        // componentes efetivas chegam ao modelo da célula
+        let poa = PoaIrradiance { beam: 700.0, sky_diffuse: 120.0, ground: 20.0 };
+        let losses = OpticalLosses::new(Iam::martin_ruiz(), SpectralModel::Sapm([0.98, 0.0, 0.0, 0.0, 0.0]));
+        let effective = losses.effective(&poa, 30.0, 30.0, ama, 1.42);
+        let (sky, ground) = losses.iam.diffuse_modifiers(30.0);
+        assert!((effective.beam - 700.0 * 0.98 * losses.iam.modifier(30.0)).abs() < 1e-9);
+        assert!((effective.sky_diffuse - 120.0 * 0.98 * sky).abs() < 1e-9 && (effective.ground - 20.0 * 0.98 * ground).abs() < 1e-9);
+        assert!(sky > ground && effective.global() < poa.global());
+        assert_eq!(OpticalLosses::new(Iam::Table(vec![(0.0, 1.0)]), SpectralModel::None).effective(&poa, 30.0, 30.0, ama, 1.42), poa);
+        let pnl = PvCell::new(&PARAMS);
+        assert_eq!(pnl.compute_state_poa(&effective, 40.0), pnl.compute_state(effective.global(), 40.0));
+    }
+}
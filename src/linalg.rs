@@ -0,0 +1,68 @@
+/// Solves `a * x = b` by Gaussian elimination with partial pivoting (`None` if singular).
+pub(crate) fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n: usize = b.len();
+    for col in 0..n {
+        let pivot: usize = (col..n).max_by(|&r, &s| a[r][col].abs().total_cmp(&a[s][col].abs()))?;
+        if a[pivot][col] == 0.0 || !a[pivot][col].is_finite() {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row: &Vec<f64> = &upper[col];
+        for (r, row) in lower.iter_mut().enumerate() {
+            let factor: f64 = row[col] / pivot_row[col];
+            if factor == 0.0 {
+                continue;
+            }
+            for (x, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * p;
+            }
+            b[col + 1 + r] -= factor * b[col];
+        }
+    }
+
+    let mut x: Vec<f64> = vec![0.0; n];
+    for row in (0..n).rev() {
+        let mut sum: f64 = b[row];
+        for k in row + 1..n {
+            sum -= a[row][k] * x[k];
+        }
+        x[row] = sum / a[row][row];
+    }
+    return Some(x);
+}
+
+/// Inverse of a square matrix (`None` if singular).
+pub(crate) fn invert(a: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n: usize = a.len();
+    let mut columns: Vec<Vec<f64>> = Vec::with_capacity(n);
+    for j in 0..n {
+        let mut e: Vec<f64> = vec![0.0; n];
+        e[j] = 1.0;
+        columns.push(solve(a.to_vec(), e)?);
+    }
+    return Some((0..n).map(|r| (0..n).map(|c| columns[c][r]).collect()).collect());
+}
+
+/// `J^T * J` and `J^T * r` of a Jacobian stored by rows (one row per residual).
+pub(crate) fn normal_equations(jac: &[Vec<f64>], r: &[f64]) -> (Vec<Vec<f64>>, Vec<f64>) {
+    let n: usize = jac.first().map_or(0, |row| row.len());
+    let mut jtj: Vec<Vec<f64>> = vec![vec![0.0; n]; n];
+    let mut jtr: Vec<f64> = vec![0.0; n];
+    for (row, &ri) in jac.iter().zip(r) {
+        for a in 0..n {
+            jtr[a] += row[a] * ri;
+            for b in a..n {
+                jtj[a][b] += row[a] * row[b];
+            }
+        }
+    }
+    for a in 1..n {
+        let (upper, lower) = jtj.split_at_mut(a);
+        for (b, row) in upper.iter().enumerate() {
+            lower[0][b] = row[a];
+        }
+    }
+    return (jtj, jtr);
+}
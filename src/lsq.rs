@@ -0,0 +1,88 @@
+use crate::linalg::{normal_equations, solve};
+
+pub(crate) struct LsqResult {
+    pub x: Vec<f64>,
+    pub residuals: Vec<f64>,
+    pub jacobian: Vec<Vec<f64>>,  // at `x`, one row per residual
+    pub cost: f64,                // sum of squared residuals
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Jacobian of `f` at `x` by central differences.
+pub(crate) fn jacobian<F>(f: &mut F, x: &[f64], m: usize) -> Vec<Vec<f64>>
+where F: FnMut(&[f64]) -> Vec<f64> {
+    let mut jac: Vec<Vec<f64>> = vec![vec![0.0; x.len()]; m];
+    let mut xp: Vec<f64> = x.to_vec();
+    for j in 0..x.len() {
+        let h: f64 = 1e-6 * x[j].abs().max(1e-3);
+        xp[j] = x[j] + h;
+        let fp: Vec<f64> = f(&xp);
+        xp[j] = x[j] - h;
+        let fm: Vec<f64> = f(&xp);
+        xp[j] = x[j];
+        for (row, (p, q)) in jac.iter_mut().zip(fp.iter().zip(fm.iter())) {
+            row[j] = (p - q) / (2.0 * h);
+        }
+    }
+    return jac;
+}
+
+fn cost(r: &[f64]) -> f64 {
+    let c: f64 = r.iter().map(|ri| ri * ri).sum();
+    return if c.is_nan() { f64::INFINITY } else { c };
+}
+
+/// Levenberg-Marquardt minimization of `sum(f(x)^2)`, with Marquardt's diagonal scaling.
+///
+/// Stops when the cost drops below `tol_cost` or at a local minimum (no step reduces the cost,
+/// or the relative step is below `1e-12`); `converged` is false only if `max_iter` was reached.
+pub(crate) fn levenberg_marquardt<F>(mut f: F, x0: &[f64], tol_cost: f64, max_iter: usize) -> LsqResult
+where F: FnMut(&[f64]) -> Vec<f64> {
+    let mut x: Vec<f64> = x0.to_vec();
+    let mut r: Vec<f64> = f(&x);
+    let m: usize = r.len();
+    let mut c: f64 = cost(&r);
+    let mut lambda: f64 = 1e-3;
+    let mut jac: Vec<Vec<f64>> = jacobian(&mut f, &x, m);
+
+    for iterations in 1..=max_iter {
+        if c <= tol_cost {
+            return LsqResult { x, residuals: r, jacobian: jac, cost: c, iterations, converged: true };
+        }
+        let (jtj, jtr) = normal_equations(&jac, &r);
+
+        let mut accepted: bool = false;
+        let mut small_step: bool = false;
+        while lambda < 1e16 {
+            let mut a: Vec<Vec<f64>> = jtj.clone();
+            for (k, row) in a.iter_mut().enumerate() {
+                row[k] += lambda * jtj[k][k].max(1e-12);
+            }
+            let neg_g: Vec<f64> = jtr.iter().map(|g| -g).collect();
+            let Some(dx) = solve(a, neg_g) else {
+                lambda *= 10.0;
+                continue;
+            };
+            let x_new: Vec<f64> = x.iter().zip(dx.iter()).map(|(xi, d)| xi + d).collect();
+            let r_new: Vec<f64> = f(&x_new);
+            let c_new: f64 = cost(&r_new);
+            if c_new < c {
+                small_step = dx.iter().zip(x.iter()).all(|(d, xi)| d.abs() <= 1e-12 * xi.abs().max(1e-12));
+                x = x_new;
+                r = r_new;
+                c = c_new;
+                lambda = (lambda / 3.0).max(1e-12);
+                accepted = true;
+                break;
+            }
+            lambda *= 4.0;
+        }
+        jac = jacobian(&mut f, &x, m);
+        if !accepted || small_step {
+            // mínimo local: nenhum passo reduz o custo
+            return LsqResult { x, residuals: r, jacobian: jac, cost: c, iterations, converged: true };
+        }
+    }
+    return LsqResult { x, residuals: r, jacobian: jac, cost: c, iterations: max_iter, converged: c <= tol_cost };
+}
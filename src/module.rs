@@ -0,0 +1,515 @@
+use std::fmt;
+use crate::pvcell::{BypassDiode, PvCell, PvCellState};
+use crate::series::{Series, SeriesMethod, SeriesSolver};
+use crate::parallel::{Parallel, ParallelSolver};
+use crate::element::Element;
+use crate::error::{OperatingPoint, SolverError};
+use crate::stats::SolverStats;
+use crate::roots::{Root, brent, bracket_decreasing};
+use crate::mpp::{Mpp, global_mpp};
+use crate::small_signal::SmallSignal;
+use crate::curve::{CurveOptions, IvCurve, build_curve};
+use std::convert::Infallible;
+use tracing::{warn, error};
+
+const MPP_GRID_PER_SUBSTRING: usize = 20;
+const MPP_GRID_MIN: usize = 50;
+
+/// State of a [`Substring`]: cell states `[string][cell]` and the bypass diode at the cell temperature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubstringState {
+    pub cells: Vec<Vec<PvCellState>>,
+    pub bypass: BypassDiode,
+}
+
+impl SubstringState {
+    /// Current of the bypass diode when the substring is at voltage `v`.
+    pub fn bypass_current(&self, v: f64) -> f64 {
+        return self.bypass.i_from_v(-v);
+    }
+}
+
+/// Cells between the terminals of one bypass diode: one or more strings in parallel.
+///
+/// `bypass` is given at its reference temperature; the states carry it at the cell temperature.
+#[derive(Clone)]
+pub struct Substring {
+    pub cells: Parallel,
+    pub bypass: BypassDiode,
+}
+
+impl Substring {
+    pub fn new(cells: Parallel, bypass: BypassDiode) -> Self {
+        return Substring { cells, bypass };
+    }
+
+    pub fn n_cells(&self) -> usize {
+        return self.cells.elements.iter().map(|s| s.len()).sum();
+    }
+
+    /// State with the cell states `cells` and the bypass diode at `cell_temp` [°C].
+    pub fn state(&self, cells: Vec<Vec<PvCellState>>, cell_temp: f64) -> SubstringState {
+        return SubstringState { cells, bypass: self.bypass.at_temperature(cell_temp) };
+    }
+
+    fn v_from_i_impl(&self, state: &SubstringState, i: f64, solver: &ModuleSolver, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let root = if self.cells.len() == 1 {
+            self.split_current(state, i, solver, strict, stats)?
+        } else {
+            self.shared_voltage(state, i, solver, strict, stats)?
+        };
+        let Some((v, root)) = root else {
+            stats.module.record(0, f64::NAN, false);
+            return Err(SolverError::not_converged(0, f64::NAN, solver.tol_v, OperatingPoint::Current(i), f64::NAN));
+        };
+        stats.module.record(root.iterations, root.fx.abs(), root.converged);
+        if !root.converged {
+            return Err(SolverError::not_converged(root.iterations, root.fx.abs(), solver.tol_v, OperatingPoint::Current(i), v));
+        }
+        return Ok(v);
+    }
+
+    /// Single string: finds the cell current `i_c` at which the string voltage equals minus the
+    /// diode voltage at `i - i_c` (both explicit in the current).
+    fn split_current(&self, state: &SubstringState, i: f64, solver: &ModuleSolver, strict: bool, stats: &mut SolverStats) -> Result<Option<(f64, Root)>, SolverError> {
+        let string: &Series = &self.cells.elements[0];
+        let (states, bypass): (&[PvCellState], &BypassDiode) = (&state.cells[0], &state.bypass);
+        let mut v_cells = |i_c: f64| -> Result<f64, SolverError> {
+            if strict {
+                string.try_v_from_i_with_stats(states, i_c, stats)
+            } else {
+                Ok(string.v_from_i_lenient(states, i_c, stats))
+            }
+        };
+
+        // diodo reversamente polarizado: toda a corrente (menos i_s) passa pelas células
+        let i_max: f64 = i + bypass.i_s * (1.0 - 1e-9);
+        let v_max: f64 = v_cells(i_max)?;
+        if v_max + bypass.v_from_i(i - i_max) >= 0.0 {
+            return Ok(Some((v_max, Root { x: i_max, fx: 0.0, iterations: 0, converged: true })));
+        }
+
+        let mut residual = |i_c: f64| -> Result<f64, SolverError> {
+            Ok(v_cells(i_c)? + bypass.v_from_i(i - i_c))
+        };
+        let Some((lo, hi, f_lo, f_hi)) = bracket_decreasing(&mut residual, i.min(0.0) - 1.0, i_max, 60)? else {
+            return Ok(None);
+        };
+        let root = brent(&mut residual, lo, hi, f_lo, f_hi, solver.tol_v, solver.max_iter)?;
+        return Ok(Some((-bypass.v_from_i(i - root.x), root)));
+    }
+
+    /// Strings in parallel: finds the substring voltage at which the string currents and the
+    /// bypass current add up to `i`.
+    fn shared_voltage(&self, state: &SubstringState, i: f64, solver: &ModuleSolver, strict: bool, stats: &mut SolverStats) -> Result<Option<(f64, Root)>, SolverError> {
+        let states: &[Vec<PvCellState>] = &state.cells;
+        let mut residual = |v: f64| -> Result<f64, SolverError> {
+            let mut i_sub: f64 = state.bypass_current(v) - i;
+            for (k, string) in self.cells.elements.iter().enumerate() {
+                i_sub += if strict {
+                    string.try_i_from_v_with_stats(&states[k], v, stats)?
+                } else {
+                    string.i_from_v_lenient(&states[k], v, stats)
+                };
+            }
+            Ok(i_sub)
+        };
+        let Some((lo, hi, f_lo, f_hi)) = bracket_decreasing(&mut residual, -1.0, 1.0, 60)? else {
+            return Ok(None);
+        };
+        let root = brent(&mut residual, lo, hi, f_lo, f_hi, solver.tol_v, solver.max_iter)?;
+        return Ok(Some((root.x, root)));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleSolver {
+    pub max_iter: usize,    // max number of iterations of each substring and module solve
+    pub tol_v: f64,         // [V] voltage tolerance
+}
+
+impl Default for ModuleSolver {
+    fn default() -> Self {
+        ModuleSolver { max_iter: 100, tol_v: 1e-3 }
+    }
+}
+
+/// Module modelled cell by cell: substrings in series, each with its own bypass diode.
+///
+/// States are one [`SubstringState`] per substring, with the cells indexed `[string][cell]`.
+#[derive(Clone)]
+pub struct Module {
+    pub substrings: Vec<Substring>,
+    pub solver: ModuleSolver,
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Module[")?;
+        for s in self.substrings.iter() {
+            writeln!(f, "  {}x{} cells, {:?}, ", s.cells.len(), s.n_cells() / s.cells.len().max(1), s.bypass)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl Module {
+    pub fn new(substrings: Vec<Substring>) -> Self {
+        return Module { substrings, solver: ModuleSolver::default() };
+    }
+
+    /// `n_cells` cells in series, split in `n_substrings` equal substrings (e.g. 60 or 72 cells, 3 substrings).
+    ///
+    /// `cell` is a single cell (see [`PvCell::split_series`]); its own `v_bypass` is disabled.
+    pub fn standard(cell: &PvCell, n_cells: usize, n_substrings: usize, bypass: BypassDiode) -> Self {
+        assert!(n_substrings > 0 && n_cells.is_multiple_of(n_substrings), "Module::standard: {} células não divisíveis em {} substrings", n_cells, n_substrings);
+        return Module::from_layout(cell.clone().without_bypass(), n_cells / n_substrings, 1, n_substrings, bypass);
+    }
+
+    /// Half-cut layout: `n_cells` half cells (e.g. 120 or 144); each of the `n_substrings`
+    /// substrings is made of two strings in parallel (upper and lower half of the module).
+    ///
+    /// `cell` is a full-size cell; the half cells are obtained with [`PvCell::split_parallel`].
+    pub fn half_cut(cell: &PvCell, n_cells: usize, n_substrings: usize, bypass: BypassDiode) -> Self {
+        assert!(n_substrings > 0 && n_cells.is_multiple_of(2 * n_substrings), "Module::half_cut: {} meias células não divisíveis em {} substrings", n_cells, n_substrings);
+        return Module::from_layout(cell.split_parallel(2).without_bypass(), n_cells / (2 * n_substrings), 2, n_substrings, bypass);
+    }
+
+    fn from_layout(cell: PvCell, cells_per_string: usize, strings: usize, n_substrings: usize, bypass: BypassDiode) -> Self {
+        let string = Series::new(vec![cell; cells_per_string]).with_solver(SeriesSolver { tol_v: 1e-4, method: SeriesMethod::Brent, ..SeriesSolver::default() });
+        let cells = Parallel::new(vec![string; strings]).with_solver(ParallelSolver { tol_i: 1e-4, ..ParallelSolver::default() });
+        return Module::new(vec![Substring::new(cells, bypass); n_substrings]);
+    }
+
+    /// builder
+    pub fn with_solver(mut self, settings: ModuleSolver) -> Self { self.solver = settings; return self; }
+
+    pub fn len(&self) -> usize {
+        return self.substrings.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.substrings.is_empty();
+    }
+
+    pub fn n_cells(&self) -> usize {
+        return self.substrings.iter().map(|s| s.n_cells()).sum();
+    }
+
+    pub fn states_uniform_conditions(&self, irrad_ef: f64, cell_temp: f64) -> Vec<SubstringState> {
+        let mut states: Vec<SubstringState> = Vec::with_capacity(self.len());
+        for sub in self.substrings.iter() {
+            states.push(sub.state(sub.cells.states_uniform_conditions(irrad_ef, cell_temp), cell_temp));
+        }
+        return states;
+    }
+
+    /// One irradiance and temperature per cell, in `[substring][string][cell]` order; each bypass
+    /// diode is at the mean temperature of the cells it protects.
+    pub fn states_conditions(&self, irrad_ef: &[f64], cell_temp: &[f64]) -> Vec<SubstringState> {
+        assert_eq!(irrad_ef.len(), self.n_cells(), "Module::states_conditions: uma irradiância por célula");
+        assert_eq!(cell_temp.len(), self.n_cells(), "Module::states_conditions: uma temperatura por célula");
+        let mut irrad_ef = irrad_ef.iter();
+        let mut cell_temp = cell_temp.iter();
+        let mut states: Vec<SubstringState> = Vec::with_capacity(self.len());
+        for sub in self.substrings.iter() {
+            let mut t_sum: f64 = 0.0;
+            let mut strings: Vec<Vec<PvCellState>> = Vec::with_capacity(sub.cells.len());
+            for string in sub.cells.elements.iter() {
+                strings.push(string.iter().map(|c| {
+                    let t: f64 = *cell_temp.next().unwrap();
+                    t_sum += t;
+                    c.compute_state(*irrad_ef.next().unwrap(), t)
+                }).collect());
+            }
+            states.push(sub.state(strings, t_sum / sub.n_cells() as f64));
+        }
+        return states;
+    }
+
+    /// Uniform conditions with one shading fraction per cell, in `[substring][string][cell]` order.
+    pub fn states_shading(&self, irrad_ef: f64, cell_temp: f64, shading: &[f64]) -> Vec<SubstringState> {
+        assert_eq!(shading.len(), self.n_cells(), "Module::states_shading: um sombreamento por célula");
+        let mut shading = shading.iter();
+        let mut states: Vec<SubstringState> = Vec::with_capacity(self.len());
+        for sub in self.substrings.iter() {
+            let mut strings: Vec<Vec<PvCellState>> = Vec::with_capacity(sub.cells.len());
+            for string in sub.cells.elements.iter() {
+                strings.push(string.iter().map(|c| c.compute_state(irrad_ef * (1.0 - shading.next().unwrap()), cell_temp)).collect());
+            }
+            states.push(sub.state(strings, cell_temp));
+        }
+        return states;
+    }
+
+    pub fn vs_from_i(&self, states: &[SubstringState], i: f64) -> Vec<f64> {
+        self.vs_from_i_lenient(states, i, &mut SolverStats::new())
+    }
+
+    pub fn try_vs_from_i(&self, states: &[SubstringState], i: f64) -> Result<Vec<f64>, SolverError> {
+        self.try_vs_from_i_with_stats(states, i, &mut SolverStats::new())
+    }
+
+    /// Voltage of each substring at the module current `i`.
+    pub fn try_vs_from_i_with_stats(&self, states: &[SubstringState], i: f64, stats: &mut SolverStats) -> Result<Vec<f64>, SolverError> {
+        let mut voltages: Vec<f64> = Vec::with_capacity(self.len());
+        for (k, sub) in self.substrings.iter().enumerate() {
+            voltages.push(sub.v_from_i_impl(&states[k], i, &self.solver, true, stats)?);
+        }
+        return Ok(voltages);
+    }
+
+    /// Logs non-converged substrings and keeps their last iterates.
+    pub(crate) fn vs_from_i_lenient(&self, states: &[SubstringState], i: f64, stats: &mut SolverStats) -> Vec<f64> {
+        let mut voltages: Vec<f64> = Vec::with_capacity(self.len());
+        for (k, sub) in self.substrings.iter().enumerate() {
+            voltages.push(match sub.v_from_i_impl(&states[k], i, &self.solver, false, stats) {
+                Ok(v) => v,
+                Err(e) => {
+                    let v = e.last_value();
+                    if v.is_normal() {
+                        warn!("({:p}) Module::v_from_i(i={:e}) substring {} nao convergiu (tol={:e}, max_iter={}) -> (v={})",
+                            &self, i, k, self.solver.tol_v, self.solver.max_iter, v);
+                    } else {
+                        error!("({:p}) Module::v_from_i(i={:e}) substring {} nao convergiu (tol={:e}, max_iter={}) -> (v={})",
+                            &self, i, k, self.solver.tol_v, self.solver.max_iter, v);
+                    }
+                    v
+                }
+            });
+        }
+        return voltages;
+    }
+
+    /// Bypass diode currents for the substring voltages returned by `vs_from_i`.
+    pub fn bypass_currents(&self, states: &[SubstringState], voltages: &[f64]) -> Vec<f64> {
+        return states.iter().zip(voltages).map(|(s, &v)| s.bypass_current(v)).collect();
+    }
+
+    pub fn v_from_i(&self, states: &[SubstringState], i: f64) -> f64 {
+        self.v_from_i_lenient(states, i, &mut SolverStats::new())
+    }
+
+    /// Lenient, accumulating the solver statistics in `stats`.
+    pub fn v_from_i_with_stats(&self, states: &[SubstringState], i: f64, stats: &mut SolverStats) -> f64 {
+        self.v_from_i_lenient(states, i, stats)
+    }
+
+    pub fn try_v_from_i(&self, states: &[SubstringState], i: f64) -> Result<f64, SolverError> {
+        self.try_v_from_i_with_stats(states, i, &mut SolverStats::new())
+    }
+
+    pub fn try_v_from_i_with_stats(&self, states: &[SubstringState], i: f64, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let voltages = self.try_vs_from_i_with_stats(states, i, stats)?;
+        return Ok(voltages.iter().sum());
+    }
+
+    pub(crate) fn v_from_i_lenient(&self, states: &[SubstringState], i: f64, stats: &mut SolverStats) -> f64 {
+        let voltages = self.vs_from_i_lenient(states, i, stats);
+        return voltages.iter().sum();
+    }
+
+    pub fn i_from_v(&self, states: &[SubstringState], v: f64) -> f64 {
+        self.i_from_v_lenient(states, v, &mut SolverStats::new())
+    }
+
+    /// Lenient, accumulating the solver statistics in `stats`.
+    pub fn i_from_v_with_stats(&self, states: &[SubstringState], v: f64, stats: &mut SolverStats) -> f64 {
+        self.i_from_v_lenient(states, v, stats)
+    }
+
+    pub fn try_i_from_v(&self, states: &[SubstringState], v: f64) -> Result<f64, SolverError> {
+        self.try_i_from_v_with_stats(states, v, &mut SolverStats::new())
+    }
+
+    /// Fails if the module iteration or any of the substring solves does not converge.
+    pub fn try_i_from_v_with_stats(&self, states: &[SubstringState], v: f64, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        self.i_from_v_impl(states, v, true, stats)
+    }
+
+    /// Logs a non-converged solve and returns its last iterate.
+    pub(crate) fn i_from_v_lenient(&self, states: &[SubstringState], v: f64, stats: &mut SolverStats) -> f64 {
+        match self.i_from_v_impl(states, v, false, stats) {
+            Ok(i) => i,
+            Err(e) => {
+                let i = e.last_value();
+                if i.is_normal() {
+                    warn!("({:p}) Module::i_from_v(v={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
+                        &self, v, self.solver.tol_v, self.solver.max_iter, i);
+                } else {
+                    error!("({:p}) Module::i_from_v(v={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
+                        &self, v, self.solver.tol_v, self.solver.max_iter, i);
+                }
+                i
+            }
+        }
+    }
+
+    /// `strict`: propagate failures of the substring solves instead of using their last iterates
+    fn i_from_v_impl(&self, states: &[SubstringState], v: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if self.is_empty() {
+            return Err(SolverError::not_converged(0, f64::NAN, self.solver.tol_v, OperatingPoint::Voltage(v), f64::NAN));
+        }
+
+        // a tensão do módulo decresce com a corrente
+        let mut residual = |i: f64| -> Result<f64, SolverError> {
+            let v_mod = if strict {
+                self.try_v_from_i_with_stats(states, i, stats)?
+            } else {
+                self.v_from_i_lenient(states, i, stats)
+            };
+            Ok(v_mod - v)
+        };
+
+        let root = match bracket_decreasing(&mut residual, 0.0, 1.0, 60)? {
+            Some((lo, hi, f_lo, f_hi)) => brent(&mut residual, lo, hi, f_lo, f_hi, self.solver.tol_v, self.solver.max_iter)?,
+            None => {
+                stats.module.record(0, f64::NAN, false);
+                return Err(SolverError::not_converged(0, f64::NAN, self.solver.tol_v, OperatingPoint::Voltage(v), f64::NAN));
+            }
+        };
+        stats.module.record(root.iterations, root.fx.abs(), root.converged);
+        if !root.converged {
+            return Err(SolverError::not_converged(root.iterations, root.fx.abs(), self.solver.tol_v, OperatingPoint::Voltage(v), root.x));
+        }
+        return Ok(root.x);
+    }
+
+    /// dV/dI [Ohm] of the module at the current `i` with substring voltages `voltages`:
+    /// each substring contributes its cells and its bypass diode in parallel.
+    fn dv_di(&self, states: &[SubstringState], voltages: &[f64], strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let mut r: f64 = 0.0;
+        for (k, sub) in self.substrings.iter().enumerate() {
+            let (_, g_cells) = sub.cells.i_and_di_dv(&states[k].cells, voltages[k], strict, stats)?;
+            let g_b: f64 = states[k].bypass.conductance(states[k].bypass_current(voltages[k]));
+            r += 1.0 / (g_cells - g_b);
+        }
+        return Ok(r);
+    }
+
+    pub fn small_signal(&self, states: &[SubstringState], v: f64) -> SmallSignal {
+        let mut stats = SolverStats::new();
+        let i: f64 = self.i_from_v_lenient(states, v, &mut stats);
+        let voltages = self.vs_from_i_lenient(states, i, &mut stats);
+        let r: f64 = self.dv_di(states, &voltages, false, &mut stats).unwrap_or(f64::NAN);
+        return SmallSignal { v, i, g: 1.0 / r };
+    }
+
+    pub fn try_small_signal(&self, states: &[SubstringState], v: f64) -> Result<SmallSignal, SolverError> {
+        self.try_small_signal_with_stats(states, v, &mut SolverStats::new())
+    }
+
+    pub fn try_small_signal_with_stats(&self, states: &[SubstringState], v: f64, stats: &mut SolverStats) -> Result<SmallSignal, SolverError> {
+        let i: f64 = self.try_i_from_v_with_stats(states, v, stats)?;
+        let voltages = self.try_vs_from_i_with_stats(states, i, stats)?;
+        let r: f64 = self.dv_di(states, &voltages, true, stats)?;
+        return Ok(SmallSignal { v, i, g: 1.0 / r });
+    }
+
+    /// Global MPP, also on multi-peak curves caused by the bypass diodes.
+    pub fn mpp(&self, states: &[SubstringState]) -> Mpp {
+        let mut stats = SolverStats::new();
+        let v_oc: f64 = self.v_from_i_lenient(states, 0.0, &mut stats);
+        let Ok(mpp) = global_mpp(|v| Ok::<f64, Infallible>(self.i_from_v_lenient(states, v, &mut stats)), v_oc, self.mpp_grid());
+        return mpp;
+    }
+
+    pub fn try_mpp(&self, states: &[SubstringState]) -> Result<Mpp, SolverError> {
+        self.try_mpp_with_stats(states, &mut SolverStats::new())
+    }
+
+    pub fn try_mpp_with_stats(&self, states: &[SubstringState], stats: &mut SolverStats) -> Result<Mpp, SolverError> {
+        let v_oc: f64 = self.try_v_from_i_with_stats(states, 0.0, stats)?;
+        return global_mpp(|v| self.try_i_from_v_with_stats(states, v, stats), v_oc, self.mpp_grid());
+    }
+
+    /// MPP under uniform conditions, together with the states it was computed from.
+    pub fn mpp_at(&self, irrad_ef: f64, cell_temp: f64) -> (Mpp, Vec<SubstringState>) {
+        let states = self.states_uniform_conditions(irrad_ef, cell_temp);
+        return (self.mpp(&states), states);
+    }
+
+    pub fn iv_curve(&self, states: &[SubstringState], opts: &CurveOptions) -> IvCurve {
+        let mut stats = SolverStats::new();
+        let isc: f64 = self.i_from_v_lenient(states, 0.0, &mut stats);
+        let voc: f64 = self.v_from_i_lenient(states, 0.0, &mut stats);
+        let mpp: Mpp = self.mpp(states);
+        let Ok(curve) = build_curve(|v| Ok::<f64, Infallible>(self.i_from_v_lenient(states, v, &mut stats)), isc, voc, mpp, opts);
+        return curve;
+    }
+
+    pub fn try_iv_curve(&self, states: &[SubstringState], opts: &CurveOptions) -> Result<IvCurve, SolverError> {
+        self.try_iv_curve_with_stats(states, opts, &mut SolverStats::new())
+    }
+
+    pub fn try_iv_curve_with_stats(&self, states: &[SubstringState], opts: &CurveOptions, stats: &mut SolverStats) -> Result<IvCurve, SolverError> {
+        let isc: f64 = self.try_i_from_v_with_stats(states, 0.0, stats)?;
+        let voc: f64 = self.try_v_from_i_with_stats(states, 0.0, stats)?;
+        let mpp: Mpp = self.try_mpp_with_stats(states, stats)?;
+        return build_curve(|v| self.try_i_from_v_with_stats(states, v, stats), isc, voc, mpp, opts);
+    }
+}
+
+impl Element for Module {
+    type State = Vec<SubstringState>;
+
+    fn compute_state(&self, irrad_ef: f64, cell_temp: f64) -> Self::State {
+        return self.states_uniform_conditions(irrad_ef, cell_temp);
+    }
+
+    fn current_at(&self, states: &Self::State, v: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if strict {
+            return self.try_i_from_v_with_stats(states, v, stats);
+        }
+        return Ok(self.i_from_v_lenient(states, v, stats));
+    }
+
+    fn voltage_at(&self, states: &Self::State, i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if strict {
+            return self.try_v_from_i_with_stats(states, i, stats);
+        }
+        return Ok(self.v_from_i_lenient(states, i, stats));
+    }
+
+    fn conductance_at(&self, states: &Self::State, _v: f64, i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let voltages = if strict {
+            self.try_vs_from_i_with_stats(states, i, stats)?
+        } else {
+            self.vs_from_i_lenient(states, i, stats)
+        };
+        return Ok(1.0 / self.dv_di(states, &voltages, strict, stats)?);
+    }
+
+    fn nominal_voc(&self) -> f64 {
+        return self.substrings.iter().map(|s| s.cells.nominal_voc()).sum();
+    }
+
+    fn nominal_il(&self) -> f64 {
+        return self.substrings.iter().map(|s| s.cells.nominal_il()).fold(f64::INFINITY, f64::min);
+    }
+
+    fn mpp_grid(&self) -> usize {
+        return (MPP_GRID_PER_SUBSTRING * self.len()).max(MPP_GRID_MIN);
+    }
+
+    fn reduced(&self) -> Self {
+        return self.clone();
+    }
+
+    /// Modules are not compared: `None`, they are never merged.
+    fn series_ratio(&self, _other: &Self) -> Option<f64> {
+        return None;
+    }
+
+    fn parallel_ratio(&self, _other: &Self) -> Option<f64> {
+        return None;
+    }
+
+    fn merge_series(&mut self, _other: &Self) -> bool {
+        return false;
+    }
+
+    fn merge_parallel(&mut self, _other: &Self) -> bool {
+        return false;
+    }
+}
@@ -0,0 +1,78 @@
+/// Maximum power point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mpp {
+    pub v: f64, // [V]
+    pub i: f64, // [A]
+    pub p: f64, // [W]
+}
+
+const INV_PHI: f64 = 0.618_033_988_749_895; // (sqrt(5) - 1) / 2
+const REL_TOL_V: f64 = 1e-6;
+const MAX_ITER: usize = 200;
+
+/// Global maximum of `v * i(v)` on `[0, v_oc]`.
+///
+/// The curve is sampled on `n_grid` intervals and every local maximum of the samples is refined
+/// by golden-section search, so secondary peaks (bypass diodes under partial shading) are not
+/// mistaken for the global one as long as the grid resolves them.
+pub(crate) fn global_mpp<F, E>(mut i_from_v: F, v_oc: f64, n_grid: usize) -> Result<Mpp, E>
+where F: FnMut(f64) -> Result<f64, E> {
+    if v_oc.is_nan() || v_oc <= 0.0 {
+        let i: f64 = i_from_v(0.0)?;
+        return Ok(Mpp { v: 0.0, i, p: 0.0 });
+    }
+    let n: usize = n_grid.max(2);
+
+    let mut grid: Vec<Mpp> = Vec::with_capacity(n + 1);
+    for k in 0..=n {
+        let v: f64 = v_oc * (k as f64) / (n as f64);
+        let i: f64 = i_from_v(v)?;
+        grid.push(Mpp { v, i, p: v * i });
+    }
+
+    let tol: f64 = REL_TOL_V * v_oc;
+    let mut best: Mpp = grid[0];
+    for k in 1..n {
+        if grid[k].p >= grid[k - 1].p && grid[k].p >= grid[k + 1].p {
+            let peak: Mpp = golden_section(&mut i_from_v, grid[k - 1].v, grid[k + 1].v, tol)?;
+            let peak: Mpp = if peak.p >= grid[k].p { peak } else { grid[k] };
+            if peak.p > best.p {
+                best = peak;
+            }
+        }
+    }
+    if grid[n].p > best.p {
+        best = grid[n];
+    }
+    return Ok(best);
+}
+
+fn golden_section<F, E>(i_from_v: &mut F, mut a: f64, mut b: f64, tol: f64) -> Result<Mpp, E>
+where F: FnMut(f64) -> Result<f64, E> {
+    let mut c: f64 = b - INV_PHI * (b - a);
+    let mut d: f64 = a + INV_PHI * (b - a);
+    let mut ic: f64 = i_from_v(c)?;
+    let mut id: f64 = i_from_v(d)?;
+    for _ in 0..MAX_ITER {
+        if (b - a).abs() <= tol {
+            break;
+        }
+        if c * ic > d * id {
+            b = d;
+            d = c;
+            id = ic;
+            c = b - INV_PHI * (b - a);
+            ic = i_from_v(c)?;
+        } else {
+            a = c;
+            c = d;
+            ic = id;
+            d = a + INV_PHI * (b - a);
+            id = i_from_v(d)?;
+        }
+    }
+    if c * ic > d * id {
+        return Ok(Mpp { v: c, i: ic, p: c * ic });
+    }
+    return Ok(Mpp { v: d, i: id, p: d * id });
+}
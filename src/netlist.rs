@@ -0,0 +1,438 @@
+use crate::pvcell::{BypassDiode, PvCell, PvCellState, C_TO_K, T_REF};
+use crate::error::{OperatingPoint, SolverError};
+use crate::stats::SolverStats;
+use crate::linalg::solve;
+use tracing::{warn, error};
+
+/// Index of a node of a [`Netlist`].
+pub type NodeId = usize;
+
+/// Reference node (0 V), present in every netlist.
+pub const GROUND: NodeId = 0;
+
+const MAX_HALVINGS: usize = 30;
+
+/// Residual vector and Jacobian of the MNA system.
+type Residuals = (Vec<f64>, Vec<Vec<f64>>);
+
+/// State of a [`Netlist`]: cell states and diodes at their operating temperature, each in the
+/// order the components were added.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetlistState {
+    pub cells: Vec<PvCellState>,
+    pub diodes: Vec<BypassDiode>,
+}
+
+/// Component between two nodes. `currents[k]` of a [`NetlistSolution`] follows the convention of each variant.
+#[derive(Debug, Clone)]
+pub enum Component {
+    Cell { cell: PvCell, pos: NodeId, neg: NodeId },                // current delivered out of `pos`
+    Diode { diode: BypassDiode, anode: NodeId, cathode: NodeId },  // forward current, anode -> cathode; reference temperature
+    Resistor { r: f64, a: NodeId, b: NodeId },                    // [Ohm] current a -> b
+    CurrentSource { i: f64, pos: NodeId, neg: NodeId },             // [A] delivered out of `pos`
+    VoltageSource { v: f64, pos: NodeId, neg: NodeId },             // [V] v(pos) - v(neg); current delivered out of `pos`
+}
+
+impl Component {
+    /// Terminals in the order of the branch voltage of the solution.
+    pub fn nodes(&self) -> (NodeId, NodeId) {
+        match self {
+            Component::Cell { pos, neg, .. } => (*pos, *neg),
+            Component::Diode { anode, cathode, .. } => (*anode, *cathode),
+            Component::Resistor { a, b, .. } => (*a, *b),
+            Component::CurrentSource { pos, neg, .. } => (*pos, *neg),
+            Component::VoltageSource { pos, neg, .. } => (*pos, *neg),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NetlistSolver {
+    pub max_iter: usize,    // max number of Newton iterations
+    pub tol_i: f64,         // [A] KCL residual of every node
+    pub tol_v: f64,         // [V] last Newton step of every node voltage
+}
+
+impl Default for NetlistSolver {
+    fn default() -> Self {
+        NetlistSolver { max_iter: 100, tol_i: 1e-6, tol_v: 1e-6 }
+    }
+}
+
+/// Operating point of a netlist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetlistSolution {
+    pub voltages: Vec<f64>,         // [V] per node, `voltages[GROUND] == 0`
+    pub branch_voltages: Vec<f64>,  // [V] per component, between the terminals of `Component::nodes`
+    pub currents: Vec<f64>,         // [A] per component
+}
+
+impl NetlistSolution {
+    /// Power [W] of component `k`: delivered by cells and sources, dissipated by diodes and resistors.
+    pub fn power(&self, k: usize) -> f64 {
+        return self.branch_voltages[k] * self.currents[k];
+    }
+}
+
+/// Circuit of PV cells, diodes, resistances and sources between arbitrary nodes (cross-ties,
+/// shared combiner buses, ...), solved by nonlinear modified nodal analysis.
+///
+/// The unknowns are the node voltages and the currents of the voltage sources; Newton's method
+/// uses the analytic conductances of the elements, with step halving on the KCL residual.
+/// Cell states are given in the order the cells were added.
+#[derive(Debug, Clone)]
+pub struct Netlist {
+    pub components: Vec<Component>,
+    pub solver: NetlistSolver,
+    n_nodes: usize,
+}
+
+impl Default for Netlist {
+    fn default() -> Self {
+        Netlist::new()
+    }
+}
+
+impl Netlist {
+    pub fn new() -> Self {
+        return Netlist { components: Vec::new(), solver: NetlistSolver::default(), n_nodes: 1 };
+    }
+
+    /// builder
+    pub fn with_solver(mut self, settings: NetlistSolver) -> Self { self.solver = settings; return self; }
+
+    /// New node, distinct from every other.
+    pub fn node(&mut self) -> NodeId {
+        self.n_nodes += 1;
+        return self.n_nodes - 1;
+    }
+
+    /// Number of nodes, `GROUND` included.
+    pub fn n_nodes(&self) -> usize {
+        return self.n_nodes;
+    }
+
+    pub fn n_cells(&self) -> usize {
+        return self.cells().count();
+    }
+
+    pub fn n_diodes(&self) -> usize {
+        return self.diodes().count();
+    }
+
+    /// Adds a component and returns its index.
+    pub fn add(&mut self, component: Component) -> usize {
+        let (p, n) = component.nodes();
+        assert!(p < self.n_nodes && n < self.n_nodes, "Netlist::add: nó inexistente ({}, {})", p, n);
+        self.components.push(component);
+        return self.components.len() - 1;
+    }
+
+    pub fn add_cell(&mut self, cell: PvCell, pos: NodeId, neg: NodeId) -> usize {
+        return self.add(Component::Cell { cell, pos, neg });
+    }
+
+    pub fn add_diode(&mut self, diode: BypassDiode, anode: NodeId, cathode: NodeId) -> usize {
+        return self.add(Component::Diode { diode, anode, cathode });
+    }
+
+    pub fn add_resistor(&mut self, r: f64, a: NodeId, b: NodeId) -> usize {
+        return self.add(Component::Resistor { r, a, b });
+    }
+
+    pub fn add_current_source(&mut self, i: f64, pos: NodeId, neg: NodeId) -> usize {
+        return self.add(Component::CurrentSource { i, pos, neg });
+    }
+
+    pub fn add_voltage_source(&mut self, v: f64, pos: NodeId, neg: NodeId) -> usize {
+        return self.add(Component::VoltageSource { v, pos, neg });
+    }
+
+    /// Sets the value of the voltage or current source `k`.
+    pub fn set_source(&mut self, k: usize, value: f64) {
+        match &mut self.components[k] {
+            Component::CurrentSource { i, .. } => *i = value,
+            Component::VoltageSource { v, .. } => *v = value,
+            _ => panic!("Netlist::set_source: componente {} não é uma fonte", k),
+        }
+    }
+
+    fn cells(&self) -> impl Iterator<Item = &PvCell> {
+        self.components.iter().filter_map(|c| match c {
+            Component::Cell { cell, .. } => Some(cell),
+            _ => None,
+        })
+    }
+
+    fn diodes(&self) -> impl Iterator<Item = &BypassDiode> {
+        self.components.iter().filter_map(|c| match c {
+            Component::Diode { diode, .. } => Some(diode),
+            _ => None,
+        })
+    }
+
+    /// State with the cell states `cells` and the diodes at `diode_temp` [°C].
+    pub fn state(&self, cells: Vec<PvCellState>, diode_temp: f64) -> NetlistState {
+        return NetlistState { cells, diodes: self.diodes().map(|d| d.at_temperature(diode_temp)).collect() };
+    }
+
+    pub fn states_uniform_conditions(&self, irrad_ef: f64, cell_temp: f64) -> NetlistState {
+        return self.state(self.cells().map(|c| c.compute_state(irrad_ef, cell_temp)).collect(), cell_temp);
+    }
+
+    /// One irradiance and temperature per cell, in the order the cells were added; the diodes are
+    /// at the mean cell temperature (at the reference temperature when there are no cells).
+    pub fn states_conditions(&self, irrad_ef: &[f64], cell_temp: &[f64]) -> NetlistState {
+        assert_eq!(irrad_ef.len(), self.n_cells(), "Netlist::states_conditions: uma irradiância por célula");
+        assert_eq!(cell_temp.len(), self.n_cells(), "Netlist::states_conditions: uma temperatura por célula");
+        let cells: Vec<PvCellState> = self.cells().enumerate().map(|(k, c)| c.compute_state(irrad_ef[k], cell_temp[k])).collect();
+        let t_mean: f64 = if cell_temp.is_empty() {
+            T_REF - C_TO_K
+        } else {
+            cell_temp.iter().sum::<f64>() / cell_temp.len() as f64
+        };
+        return self.state(cells, t_mean);
+    }
+
+    pub fn solve(&self, states: &NetlistState) -> NetlistSolution {
+        self.solve_lenient(states, None, &mut SolverStats::new())
+    }
+
+    pub fn try_solve(&self, states: &NetlistState) -> Result<NetlistSolution, SolverError> {
+        self.try_solve_with_stats(states, &mut SolverStats::new())
+    }
+
+    /// Fails if the Newton iteration or any of the cell solves does not converge.
+    pub fn try_solve_with_stats(&self, states: &NetlistState, stats: &mut SolverStats) -> Result<NetlistSolution, SolverError> {
+        let x = self.solve_impl(states, None, true, stats).map_err(|(e, _)| e)?;
+        return self.solution(states, &x, true, stats);
+    }
+
+    /// Logs a non-converged solve and returns its last iterate.
+    pub(crate) fn solve_lenient(&self, states: &NetlistState, guess: Option<&[f64]>, stats: &mut SolverStats) -> NetlistSolution {
+        let x = match self.solve_impl(states, guess, false, stats) {
+            Ok(x) => x,
+            Err((e, x)) => {
+                if e.last_value().is_finite() {
+                    warn!("({:p}) Netlist::solve nao convergiu: {} (tol_i={:e}, tol_v={:e}, max_iter={})",
+                        &self, e, self.solver.tol_i, self.solver.tol_v, self.solver.max_iter);
+                } else {
+                    error!("({:p}) Netlist::solve nao convergiu: {} (tol_i={:e}, tol_v={:e}, max_iter={})",
+                        &self, e, self.solver.tol_i, self.solver.tol_v, self.solver.max_iter);
+                }
+                x
+            }
+        };
+        let n: usize = self.components.len();
+        return self.solution(states, &x, false, stats).unwrap_or_else(|_| NetlistSolution {
+            voltages: vec![f64::NAN; self.n_nodes], branch_voltages: vec![f64::NAN; n], currents: vec![f64::NAN; n],
+        });
+    }
+
+    /// Solutions with the source `source` set to each of `values`, each solve starting from the previous one.
+    pub fn sweep(&self, states: &NetlistState, source: usize, values: &[f64]) -> Vec<NetlistSolution> {
+        let mut stats = SolverStats::new();
+        let mut net: Netlist = self.clone();
+        let mut solutions: Vec<NetlistSolution> = Vec::with_capacity(values.len());
+        for &value in values {
+            net.set_source(source, value);
+            let guess: Option<Vec<f64>> = solutions.last().map(|s| self.unknowns(s));
+            solutions.push(net.solve_lenient(states, guess.as_deref(), &mut stats));
+        }
+        return solutions;
+    }
+
+    pub fn try_sweep(&self, states: &NetlistState, source: usize, values: &[f64]) -> Result<Vec<NetlistSolution>, SolverError> {
+        self.try_sweep_with_stats(states, source, values, &mut SolverStats::new())
+    }
+
+    pub fn try_sweep_with_stats(&self, states: &NetlistState, source: usize, values: &[f64], stats: &mut SolverStats) -> Result<Vec<NetlistSolution>, SolverError> {
+        let mut net: Netlist = self.clone();
+        let mut solutions: Vec<NetlistSolution> = Vec::with_capacity(values.len());
+        for &value in values {
+            net.set_source(source, value);
+            let guess: Option<Vec<f64>> = solutions.last().map(|s| self.unknowns(s));
+            let x = net.solve_impl(states, guess.as_deref(), true, stats).map_err(|(e, _)| e)?;
+            solutions.push(net.solution(states, &x, true, stats)?);
+        }
+        return Ok(solutions);
+    }
+
+    fn n_sources(&self) -> usize {
+        return self.components.iter().filter(|c| matches!(c, Component::VoltageSource { .. })).count();
+    }
+
+    /// Unknowns `[v(1), ..., v(n - 1), i(voltage sources)...]` of a solution.
+    fn unknowns(&self, sol: &NetlistSolution) -> Vec<f64> {
+        let mut x: Vec<f64> = sol.voltages[1..].to_vec();
+        for (k, c) in self.components.iter().enumerate() {
+            if let Component::VoltageSource { .. } = c {
+                x.push(sol.currents[k]);
+            }
+        }
+        return x;
+    }
+
+    /// Current [A] through component `c` from its first to its second node (passive convention)
+    /// and its derivative [S] with respect to the branch voltage `v`. Voltage sources: `(0, 0)`.
+    /// `k` is the index of the cell or diode among the components of its kind.
+    ///
+    /// `strict`: propagate failures of the cell solves instead of using their last iterates
+    fn branch(&self, c: &Component, states: &NetlistState, k: usize, v: f64, strict: bool, stats: &mut SolverStats) -> Result<(f64, f64), SolverError> {
+        match c {
+            Component::Cell { cell, .. } => {
+                let state: &PvCellState = &states.cells[k];
+                let i: f64 = if strict {
+                    cell.try_solve_i_with_stats(state, v, stats)?
+                } else {
+                    cell.solve_i_lenient(state, v, stats)
+                };
+                return Ok((-i, -cell.conductance(state, v, i)));
+            }
+            Component::Diode { .. } => {
+                let diode: &BypassDiode = &states.diodes[k];
+                let i: f64 = diode.i_from_v(v);
+                return Ok((i, diode.conductance(i)));
+            }
+            Component::Resistor { r, .. } => return Ok((v / r, 1.0 / r)),
+            Component::CurrentSource { i, .. } => return Ok((-i, 0.0)),
+            Component::VoltageSource { .. } => return Ok((0.0, 0.0)),
+        }
+    }
+
+    /// KCL residuals (current leaving each node, `GROUND` excluded) followed by the voltage source
+    /// equations, and their Jacobian (from the same cell solves).
+    fn residuals(&self, states: &NetlistState, x: &[f64], strict: bool, stats: &mut SolverStats) -> Result<Residuals, SolverError> {
+        let n_v: usize = self.n_nodes - 1;
+        let n: usize = x.len();
+        let volt = |node: NodeId| -> f64 { if node == GROUND { 0.0 } else { x[node - 1] } };
+        let mut f: Vec<f64> = vec![0.0; n];
+        let mut jac: Vec<Vec<f64>> = vec![vec![0.0; n]; n];
+        let mut cell: usize = 0;
+        let mut diode: usize = 0;
+        let mut source: usize = 0;
+        for c in self.components.iter() {
+            let (p, q) = c.nodes();
+            if let Component::VoltageSource { v, .. } = c {
+                // corrente da fonte sai de `p` para o circuito
+                let row: usize = n_v + source;
+                let i_src: f64 = x[row];
+                f[row] = volt(p) - volt(q) - v;
+                if p != GROUND { f[p - 1] -= i_src; }
+                if q != GROUND { f[q - 1] += i_src; }
+                if p != GROUND { jac[p - 1][row] -= 1.0; jac[row][p - 1] += 1.0; }
+                if q != GROUND { jac[q - 1][row] += 1.0; jac[row][q - 1] -= 1.0; }
+                source += 1;
+                continue;
+            }
+            let k: usize = match c {
+                Component::Cell { .. } => { cell += 1; cell - 1 }
+                Component::Diode { .. } => { diode += 1; diode - 1 }
+                _ => 0,
+            };
+            let (i, g) = self.branch(c, states, k, volt(p) - volt(q), strict, stats)?;
+            if p != GROUND { f[p - 1] += i; }
+            if q != GROUND { f[q - 1] -= i; }
+            if p != GROUND { jac[p - 1][p - 1] += g; }
+            if q != GROUND { jac[q - 1][q - 1] += g; }
+            if p != GROUND && q != GROUND { jac[p - 1][q - 1] -= g; jac[q - 1][p - 1] -= g; }
+        }
+        return Ok((f, jac));
+    }
+
+    /// Newton iteration on the unknowns; on failure also returns the last iterate.
+    ///
+    /// `strict`: propagate failures of the cell solves instead of using their last iterates
+    fn solve_impl(&self, states: &NetlistState, guess: Option<&[f64]>, strict: bool, stats: &mut SolverStats) -> Result<Vec<f64>, (SolverError, Vec<f64>)> {
+        assert_eq!(states.cells.len(), self.n_cells(), "Netlist::solve: um estado por célula");
+        assert_eq!(states.diodes.len(), self.n_diodes(), "Netlist::solve: um estado por diodo");
+        let n_v: usize = self.n_nodes - 1;
+        let mut x: Vec<f64> = match guess {
+            Some(g) if g.len() == n_v + self.n_sources() => g.to_vec(),
+            _ => vec![0.0; n_v + self.n_sources()],
+        };
+        let merit = |f: &[f64]| -> f64 { f.iter().map(|r| r * r).sum() };
+        let max_kcl = |f: &[f64]| -> f64 { f[..n_v].iter().fold(0.0, |m: f64, r| m.max(r.abs())) };
+        let last = |x: &[f64]| -> f64 { if x.iter().all(|v| v.is_finite()) { x.first().copied().unwrap_or(0.0) } else { f64::NAN } };
+
+        let (mut f, mut jac) = match self.residuals(states, &x, strict, stats) {
+            Ok(r) => r,
+            Err(e) => return Err((e, x)),
+        };
+        let mut iterations: usize = 0;
+        for _ in 0..self.solver.max_iter {
+            iterations += 1;
+            let minus_f: Vec<f64> = f.iter().map(|r| -r).collect();
+            let Some(dx) = solve(jac, minus_f) else {
+                stats.netlist.record(iterations, f64::NAN, false);
+                return Err((SolverError::not_converged(iterations, f64::NAN, self.solver.tol_i, OperatingPoint::Network, f64::NAN), x));
+            };
+
+            // passo de Newton reduzido à metade até o resíduo diminuir; no modo estrito, uma célula
+            // que não converge no ponto de teste também rejeita o passo
+            let m0: f64 = merit(&f);
+            let mut alpha: f64 = 1.0;
+            let mut accepted: Option<(f64, Vec<f64>, Residuals)> = None;
+            let mut failure: Option<SolverError> = None;
+            for _ in 0..MAX_HALVINGS {
+                let trial: Vec<f64> = x.iter().zip(dx.iter()).map(|(xi, di)| xi + alpha * di).collect();
+                match self.residuals(states, &trial, strict, stats) {
+                    Ok((f_trial, jac_trial)) => {
+                        let m: f64 = merit(&f_trial);
+                        let decreased: bool = m.is_finite() && m <= (1.0 - 1e-4 * alpha) * m0;
+                        accepted = Some((alpha, trial, (f_trial, jac_trial)));
+                        if decreased {
+                            break;
+                        }
+                    }
+                    Err(e) => failure = Some(e),
+                }
+                alpha /= 2.0;
+            }
+            let Some((alpha, trial, (f_trial, jac_trial))) = accepted else {
+                return Err((failure.unwrap(), x));
+            };
+            let step: f64 = dx[..n_v].iter().fold(0.0, |m: f64, d| m.max((alpha * d).abs()));
+            x = trial;
+            let residual: f64 = max_kcl(&f_trial);
+            if residual <= self.solver.tol_i && step <= self.solver.tol_v {
+                stats.netlist.record(iterations, residual, true);
+                return Ok(x);
+            }
+            if !residual.is_finite() {
+                stats.netlist.record(iterations, residual, false);
+                return Err((SolverError::not_converged(iterations, residual, self.solver.tol_i, OperatingPoint::Network, f64::NAN), x));
+            }
+            (f, jac) = (f_trial, jac_trial);
+        }
+        let residual: f64 = max_kcl(&f);
+        stats.netlist.record(iterations, residual, false);
+        let last_value: f64 = last(&x);
+        return Err((SolverError::not_converged(iterations, residual, self.solver.tol_i, OperatingPoint::Network, last_value), x));
+    }
+
+    fn solution(&self, states: &NetlistState, x: &[f64], strict: bool, stats: &mut SolverStats) -> Result<NetlistSolution, SolverError> {
+        let n_v: usize = self.n_nodes - 1;
+        let mut voltages: Vec<f64> = vec![0.0];
+        voltages.extend_from_slice(&x[..n_v]);
+        let mut branch_voltages: Vec<f64> = Vec::with_capacity(self.components.len());
+        let mut currents: Vec<f64> = Vec::with_capacity(self.components.len());
+        let mut cell: usize = 0;
+        let mut diode: usize = 0;
+        let mut source: usize = 0;
+        for c in self.components.iter() {
+            let (p, q) = c.nodes();
+            let v: f64 = voltages[p] - voltages[q];
+            let i: f64 = match c {
+                Component::Cell { .. } => { cell += 1; -self.branch(c, states, cell - 1, v, strict, stats)?.0 }
+                Component::Diode { .. } => { diode += 1; self.branch(c, states, diode - 1, v, strict, stats)?.0 }
+                Component::VoltageSource { .. } => { source += 1; x[n_v + source - 1] }
+                Component::CurrentSource { i, .. } => *i,
+                _ => self.branch(c, states, 0, v, strict, stats)?.0,
+            };
+            branch_voltages.push(v);
+            currents.push(i);
+        }
+        return Ok(NetlistSolution { voltages, branch_voltages, currents });
+    }
+}
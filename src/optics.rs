@@ -0,0 +1,155 @@
+use crate::irradiance::PoaIrradiance;
+
+/// Incidence angle modifier: transmittance at angle of incidence `aoi` relative to normal incidence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Iam {
+    /// ASHRAE: `1 - b0 (1/cos(aoi) - 1)`.
+    Ashrae { b0: f64 },
+    /// Martin & Ruiz (2001): `(1 - exp(-cos(aoi)/a_r)) / (1 - exp(-1/a_r))`.
+    MartinRuiz { a_r: f64 },
+    /// Fresnel reflection and absorption in a cover of refractive index `n`, extinction `k` [1/m] and thickness `l` [m].
+    Physical { n: f64, k: f64, l: f64 },
+    /// Linear interpolation of `(aoi [deg], modifier)` points sorted by angle, constant beyond the ends.
+    Table(Vec<(f64, f64)>),
+}
+
+impl Default for Iam {
+    fn default() -> Self {
+        Iam::physical()
+    }
+}
+
+impl Iam {
+    pub fn ashrae() -> Self {
+        return Iam::Ashrae { b0: 0.05 };
+    }
+
+    pub fn martin_ruiz() -> Self {
+        return Iam::MartinRuiz { a_r: 0.16 };
+    }
+
+    /// 2 mm of glass, `n` = 1.526.
+    pub fn physical() -> Self {
+        return Iam::Physical { n: 1.526, k: 4.0, l: 0.002 };
+    }
+
+    /// Modifier [-] at the angle of incidence `aoi` [deg]; zero from 90°, except for a `Table`,
+    /// which is used as given.
+    pub fn modifier(&self, aoi: f64) -> f64 {
+        let aoi: f64 = aoi.abs();
+        let cos_aoi: f64 = aoi.to_radians().cos();
+        match *self {
+            Iam::Table(ref points) => return interpolate(points, aoi),
+            _ if aoi >= 90.0 => return 0.0,
+            Iam::Ashrae { b0 } => return (1.0 - b0 * (1.0 / cos_aoi - 1.0)).clamp(0.0, 1.0),
+            Iam::MartinRuiz { a_r } => return (1.0 - (-cos_aoi / a_r).exp()) / (1.0 - (-1.0 / a_r).exp()),
+            Iam::Physical { n, k, l } => return cover_transmittance(n, k * l, aoi) / cover_transmittance(n, k * l, 0.0),
+        }
+    }
+
+    /// Modifiers of the sky and ground diffuse components on a plane at `tilt` [deg], at the
+    /// Brandemuehl & Beckman equivalent angles of incidence.
+    pub fn diffuse_modifiers(&self, tilt: f64) -> (f64, f64) {
+        let sky: f64 = 59.7 - 0.1388 * tilt + 0.001497 * tilt.powi(2);
+        let ground: f64 = 90.0 - 0.5788 * tilt + 0.002693 * tilt.powi(2);
+        return (self.modifier(sky), self.modifier(ground));
+    }
+}
+
+/// Transmittance of the cover, with `kl` = extinction * thickness.
+fn cover_transmittance(n: f64, kl: f64, aoi: f64) -> f64 {
+    let theta: f64 = aoi.to_radians();
+    let theta_r: f64 = (theta.sin() / n).asin();
+    let reflectance: f64 = if theta < 1e-6 {
+        ((n - 1.0) / (n + 1.0)).powi(2)  // incidência normal, limite das expressões de Fresnel
+    } else {
+        let s: f64 = ((theta_r - theta).sin() / (theta_r + theta).sin()).powi(2);
+        let p: f64 = ((theta_r - theta).tan() / (theta_r + theta).tan()).powi(2);
+        (s + p) / 2.0
+    };
+    return (-kl / theta_r.cos()).exp() * (1.0 - reflectance);
+}
+
+fn interpolate(points: &[(f64, f64)], x: f64) -> f64 {
+    assert!(!points.is_empty(), "Iam::Table: tabela vazia");
+    let k: usize = points.partition_point(|&(a, _)| a < x);
+    if k == 0 {
+        return points[0].1;
+    }
+    if k == points.len() {
+        return points[k - 1].1;
+    }
+    let ((x0, y0), (x1, y1)) = (points[k - 1], points[k]);
+    return y0 + (y1 - y0) * (x - x0) / (x1 - x0);
+}
+
+/// Spectral mismatch factor relative to the AM1.5 reference spectrum.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SpectralModel {
+    #[default]
+    None,
+    /// First Solar (Lee & Panchula, 2016), in absolute air mass `ama` and precipitable water `pw` [cm]:
+    /// `c0 + c1 ama + c2 pw + c3 sqrt(ama) + c4 sqrt(pw) + c5 ama/sqrt(pw)`.
+    FirstSolar([f64; 6]),
+    /// Sandia (King, 2004): polynomial `a0 + a1 ama + ... + a4 ama^4`, no dependence on water.
+    Sapm([f64; 5]),
+}
+
+impl SpectralModel {
+    pub fn first_solar_monosi() -> Self {
+        return SpectralModel::FirstSolar([0.85914, -0.020880, -0.0058853, 0.12029, 0.026814, -0.0017810]);
+    }
+
+    pub fn first_solar_multisi() -> Self {
+        return SpectralModel::FirstSolar([0.84090, -0.027539, -0.0079224, 0.13570, 0.038024, -0.0021218]);
+    }
+
+    pub fn first_solar_cdte() -> Self {
+        return SpectralModel::FirstSolar([0.86273, -0.038948, -0.012506, 0.098871, 0.084658, -0.0042948]);
+    }
+
+    /// Factor [-] at absolute air mass `airmass_abs` and precipitable water [cm]; 1 with the sun down (`NaN` air mass).
+    pub fn modifier(&self, airmass_abs: f64, precipitable_water: f64) -> f64 {
+        if airmass_abs.is_nan() {
+            return 1.0;
+        }
+        match *self {
+            SpectralModel::None => return 1.0,
+            SpectralModel::FirstSolar(c) => {
+                // limites de validade do ajuste
+                let ama: f64 = airmass_abs.min(10.0);
+                let pw: f64 = precipitable_water.clamp(0.1, 8.0);
+                return c[0] + c[1] * ama + c[2] * pw + c[3] * ama.sqrt() + c[4] * pw.sqrt() + c[5] * ama / pw.sqrt();
+            }
+            SpectralModel::Sapm(a) => {
+                let m: f64 = a.iter().rev().fold(0.0, |acc, c| acc * airmass_abs + c);
+                return m.max(0.0);
+            }
+        }
+    }
+}
+
+/// Optical losses between the plane of array and the cells.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OpticalLosses {
+    pub iam: Iam,
+    pub spectral: SpectralModel,
+}
+
+impl OpticalLosses {
+    pub fn new(iam: Iam, spectral: SpectralModel) -> Self {
+        return OpticalLosses { iam, spectral };
+    }
+
+    /// POA components reaching the cells: the beam through the modifier at `aoi` [deg], the diffuse
+    /// components at their equivalent angles on a plane at `tilt` [deg], all scaled by the spectral factor.
+    pub fn effective(&self, poa: &PoaIrradiance, aoi: f64, tilt: f64, airmass_abs: f64, precipitable_water: f64) -> PoaIrradiance {
+        let spectral: f64 = self.spectral.modifier(airmass_abs, precipitable_water);
+        let (sky, ground): (f64, f64) = self.iam.diffuse_modifiers(tilt);
+        return PoaIrradiance {
+            beam: poa.beam * self.iam.modifier(aoi) * spectral,
+            sky_diffuse: poa.sky_diffuse * sky * spectral,
+            ground: poa.ground * ground * spectral,
+        };
+    }
+}
@@ -1,14 +1,20 @@
 use std::fmt;
-use crate::pvcell::{PvCell, PvCellState};
-use crate::series::{Series};
-
-// pub static mut SOLVER_CALLS: usize = 0;
+use crate::series::Series;
+use crate::element::{Element, common_ratio};
+use crate::error::{OperatingPoint, SolverError};
+use crate::stats::SolverStats;
+use crate::roots::{brent, bracket_decreasing};
+use crate::mpp::{Mpp, global_mpp};
+use crate::small_signal::SmallSignal;
+use crate::curve::{CurveOptions, IvCurve, build_curve};
+use crate::thermal::{Ambient, ThermalModel};
+use std::convert::Infallible;
+use tracing::{warn, error};
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct ParallelSolver {
-    pub max_iter: usize,
-    pub tol_i: f64,
+    pub max_iter: usize,    // max number of iterations of Parallel::v_from_i
+    pub tol_i: f64,         // [A] array current tolerance
 }
 impl Default for ParallelSolver {
     fn default() -> Self {
@@ -19,27 +25,82 @@ impl Default for ParallelSolver {
     }
 }
 
+/// Elements in parallel (strings by default, any [`Element`] when nested).
 #[derive(Clone)]
-#[allow(dead_code)]
-pub struct Parallel {
-    pub elements: Vec<Series>,
+pub struct Parallel<E: Element = Series> {
+    pub elements: Vec<E>,
     pub solver: ParallelSolver,
 }
 
-impl fmt::Debug for Parallel {
+impl<E: Element + fmt::Debug> fmt::Debug for Parallel<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // write!(f, "Parallel{:?}", &self.elements)
-        write!(f, "Parallel[\n")?;
+        writeln!(f, "Parallel[")?;
         for e in self.elements.iter() {
-            write!(f, "  {:?}, \n", e)?;
+            writeln!(f, "  {:?}, ", e)?;
         }
         write!(f, "]")
     }
 }
 
-#[allow(dead_code)]
-impl Parallel {
-    pub fn new(elements: Vec<Series>) -> Self {
+/// Index maps between an array and the result of [`Parallel::reduce_map`].
+///
+/// Per-element values of the reduced array are indexed `[reduced string][reduced element]`,
+/// values of the original array `[string][module]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParallelMap {
+    pub origin_to_reduced: Vec<u32>,                // string -> reduced string
+    pub reduced_to_origin: Vec<Vec<u32>>,           // reduced string -> strings
+    pub elements_origin_to_reduced: Vec<Vec<u32>>,  // [string][module] -> element of the reduced string
+    v_share: Vec<Vec<f64>>,
+    i_share: Vec<Vec<f64>>,
+}
+
+impl ParallelMap {
+    fn expand_scaled(&self, reduced: &[Vec<f64>], scale: impl Fn(usize, usize) -> f64) -> Vec<Vec<f64>> {
+        let mut values: Vec<Vec<f64>> = Vec::with_capacity(self.origin_to_reduced.len());
+        for (s, &j) in self.origin_to_reduced.iter().enumerate() {
+            let row = self.elements_origin_to_reduced[s].iter().enumerate()
+                .map(|(m, &e)| reduced[j as usize][e as usize] * scale(s, m))
+                .collect();
+            values.push(row);
+        }
+        return values;
+    }
+
+    /// Copies intensive per-element quantities (temperature, irradiance, ...) onto the original modules.
+    pub fn expand(&self, reduced: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.expand_scaled(reduced, |_, _| 1.0)
+    }
+
+    pub fn expand_voltages(&self, reduced: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.expand_scaled(reduced, |s, m| self.v_share[s][m])
+    }
+
+    pub fn expand_currents(&self, reduced: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.expand_scaled(reduced, |s, m| self.i_share[s][m])
+    }
+
+    pub fn expand_powers(&self, reduced: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.expand_scaled(reduced, |s, m| self.v_share[s][m] * self.i_share[s][m])
+    }
+
+    /// String currents of the original array from the reduced string currents.
+    pub fn expand_string_currents(&self, reduced: &[f64]) -> Vec<f64> {
+        self.origin_to_reduced.iter().enumerate()
+            .map(|(s, &j)| reduced[j as usize] * self.i_share[s].first().copied().unwrap_or(1.0))
+            .collect()
+    }
+}
+
+impl<E: Element> Default for Parallel<E> {
+    fn default() -> Self {
+        Parallel::empty()
+    }
+}
+
+impl<E: Element> Parallel<E> {
+    pub fn new(elements: Vec<E>) -> Self {
         Parallel{ elements, solver: ParallelSolver::default() }
     }
 
@@ -56,6 +117,268 @@ impl Parallel {
         self.elements.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn push(&mut self, element: E) {
+        self.elements.push(element);
+    }
+
+    pub fn states_uniform_conditions(&self, irrad_ef: f64, cell_temp: f64) -> Vec<E::State> {
+        let mut states: Vec<E::State> = Vec::with_capacity(self.len());
+        for string in self.elements.iter(){
+            states.push(string.compute_state(irrad_ef, cell_temp));
+        }
+        return states;
+    }
+
+    /// One irradiance and temperature per string, uniform along each string.
+    pub fn states_string_conditions(&self, irrad_ef: &[f64], cell_temp: &[f64]) -> Vec<E::State> {
+        assert_eq!(irrad_ef.len(), self.len(), "Parallel::states_string_conditions: uma irradiância por string");
+        assert_eq!(cell_temp.len(), self.len(), "Parallel::states_string_conditions: uma temperatura por string");
+        let mut states: Vec<E::State> = Vec::with_capacity(self.len());
+        for (k, string) in self.elements.iter().enumerate(){
+            states.push(string.compute_state(irrad_ef[k], cell_temp[k]));
+        }
+        return states;
+    }
+
+    pub fn is_from_v(&self, states: &[E::State], v: f64) -> Vec<f64> {
+        self.is_from_v_lenient(states, v, &mut SolverStats::new())
+    }
+
+    pub fn try_is_from_v(&self, states: &[E::State], v: f64) -> Result<Vec<f64>, SolverError> {
+        self.try_is_from_v_with_stats(states, v, &mut SolverStats::new())
+    }
+
+    /// Current of each string at the array voltage `v`.
+    pub fn try_is_from_v_with_stats(&self, states: &[E::State], v: f64, stats: &mut SolverStats) -> Result<Vec<f64>, SolverError> {
+        let mut currents: Vec<f64> = Vec::with_capacity(self.len());
+        for (k, it) in self.elements.iter().enumerate() {
+            currents.push(it.current_at(&states[k], v, true, stats)?);
+        }
+        return Ok(currents);
+    }
+
+    pub(crate) fn is_from_v_lenient(&self, states: &[E::State], v: f64, stats: &mut SolverStats) -> Vec<f64> {
+        let mut currents: Vec<f64> = Vec::with_capacity(self.len());
+        for (k, it) in self.elements.iter().enumerate() {
+            currents.push(it.current_at(&states[k], v, false, stats).unwrap_or(f64::NAN));
+        }
+        return currents;
+    }
+
+    pub fn i_from_v(&self, states: &[E::State], v: f64) -> f64 {
+        self.i_from_v_lenient(states, v, &mut SolverStats::new())
+    }
+
+    /// Lenient, accumulating the solver statistics in `stats`.
+    pub fn i_from_v_with_stats(&self, states: &[E::State], v: f64, stats: &mut SolverStats) -> f64 {
+        self.i_from_v_lenient(states, v, stats)
+    }
+
+    pub fn try_i_from_v(&self, states: &[E::State], v: f64) -> Result<f64, SolverError> {
+        self.try_i_from_v_with_stats(states, v, &mut SolverStats::new())
+    }
+
+    pub fn try_i_from_v_with_stats(&self, states: &[E::State], v: f64, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let currents = self.try_is_from_v_with_stats(states, v, stats)?;
+        return Ok(currents.iter().sum());
+    }
+
+    pub(crate) fn i_from_v_lenient(&self, states: &[E::State], v: f64, stats: &mut SolverStats) -> f64 {
+        let currents = self.is_from_v_lenient(states, v, stats);
+        return currents.iter().sum();
+    }
+
+    pub fn v_from_i(&self, states: &[E::State], i: f64) -> f64 {
+        self.v_from_i_lenient(states, i, &mut SolverStats::new())
+    }
+
+    /// Lenient, accumulating the solver statistics in `stats`.
+    pub fn v_from_i_with_stats(&self, states: &[E::State], i: f64, stats: &mut SolverStats) -> f64 {
+        self.v_from_i_lenient(states, i, stats)
+    }
+
+    pub fn try_v_from_i(&self, states: &[E::State], i: f64) -> Result<f64, SolverError> {
+        self.try_v_from_i_with_stats(states, i, &mut SolverStats::new())
+    }
+
+    /// Fails if the array iteration or any of the string solves does not converge.
+    pub fn try_v_from_i_with_stats(&self, states: &[E::State], i: f64, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        self.v_from_i_impl(states, i, true, stats)
+    }
+
+    /// Logs a non-converged solve and returns its last iterate.
+    pub(crate) fn v_from_i_lenient(&self, states: &[E::State], i: f64, stats: &mut SolverStats) -> f64 {
+        match self.v_from_i_impl(states, i, false, stats) {
+            Ok(v) => v,
+            Err(e) => {
+                let v = e.last_value();
+                if v.is_normal() {
+                    warn!("({:p}) Parallel::v_from_i(i_arr={:e}) nao convergiu (tol={:e}, max_iter={}) -> (v={})",
+                        &self, i, self.solver.tol_i, self.solver.max_iter, v);
+                } else {
+                    error!("({:p}) Parallel::v_from_i(i_arr={:e}) nao convergiu (tol={:e}, max_iter={}) -> (v={})",
+                        &self, i, self.solver.tol_i, self.solver.max_iter, v);
+                }
+                v
+            }
+        }
+    }
+
+    /// `strict`: propagate failures of the string solves instead of using their last iterates
+    fn v_from_i_impl(&self, states: &[E::State], i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if self.is_empty() {
+            return Err(SolverError::not_converged(0, f64::NAN, self.solver.tol_i, OperatingPoint::Current(i), f64::NAN));
+        }
+
+        // com a corrente dividida igualmente, as tensões das strings delimitam a solução
+        let share: f64 = i / (self.len() as f64);
+        let mut v_lo: f64 = f64::INFINITY;
+        let mut v_hi: f64 = f64::NEG_INFINITY;
+        for (k, it) in self.elements.iter().enumerate() {
+            let v = it.voltage_at(&states[k], share, strict, stats)?;
+            v_lo = v_lo.min(v);
+            v_hi = v_hi.max(v);
+        }
+        if self.len() == 1 {
+            stats.parallel.record(0, 0.0, true);
+            return Ok(v_lo);
+        }
+
+        let mut residual = |v: f64| -> Result<f64, SolverError> {
+            let i_arr = if strict {
+                self.try_i_from_v_with_stats(states, v, stats)?
+            } else {
+                self.i_from_v_lenient(states, v, stats)
+            };
+            Ok(i_arr - i)
+        };
+
+        let root = match bracket_decreasing(&mut residual, v_lo, v_hi, 30)? {
+            Some((lo, hi, f_lo, f_hi)) => brent(&mut residual, lo, hi, f_lo, f_hi, self.solver.tol_i, self.solver.max_iter)?,
+            None => {
+                stats.parallel.record(0, f64::NAN, false);
+                return Err(SolverError::not_converged(0, f64::NAN, self.solver.tol_i, OperatingPoint::Current(i), f64::NAN));
+            }
+        };
+        stats.parallel.record(root.iterations, root.fx.abs(), root.converged);
+        if !root.converged {
+            return Err(SolverError::not_converged(root.iterations, root.fx.abs(), self.solver.tol_i, OperatingPoint::Current(i), root.x));
+        }
+        return Ok(root.x);
+    }
+
+    /// Array current and dI/dV [S] at the voltage `v`: sum over the strings.
+    pub(crate) fn i_and_di_dv(&self, states: &[E::State], v: f64, strict: bool, stats: &mut SolverStats) -> Result<(f64, f64), SolverError> {
+        let currents = if strict {
+            self.try_is_from_v_with_stats(states, v, stats)?
+        } else {
+            self.is_from_v_lenient(states, v, stats)
+        };
+        let mut g: f64 = 0.0;
+        for (k, it) in self.elements.iter().enumerate() {
+            g += it.conductance_at(&states[k], v, currents[k], strict, stats)?;
+        }
+        return Ok((currents.iter().sum(), g));
+    }
+
+    pub fn small_signal(&self, states: &[E::State], v: f64) -> SmallSignal {
+        let (i, g) = self.i_and_di_dv(states, v, false, &mut SolverStats::new()).unwrap_or((f64::NAN, f64::NAN));
+        return SmallSignal { v, i, g };
+    }
+
+    pub fn try_small_signal(&self, states: &[E::State], v: f64) -> Result<SmallSignal, SolverError> {
+        self.try_small_signal_with_stats(states, v, &mut SolverStats::new())
+    }
+
+    pub fn try_small_signal_with_stats(&self, states: &[E::State], v: f64, stats: &mut SolverStats) -> Result<SmallSignal, SolverError> {
+        let (i, g) = self.i_and_di_dv(states, v, true, stats)?;
+        return Ok(SmallSignal { v, i, g });
+    }
+
+    /// Global MPP, also on multi-peak curves caused by bypass diodes.
+    pub fn mpp(&self, states: &[E::State]) -> Mpp {
+        let mut stats = SolverStats::new();
+        let v_oc: f64 = self.v_from_i_lenient(states, 0.0, &mut stats);
+        let Ok(mpp) = global_mpp(|v| Ok::<f64, Infallible>(self.i_from_v_lenient(states, v, &mut stats)), v_oc, self.mpp_grid());
+        return mpp;
+    }
+
+    pub fn try_mpp(&self, states: &[E::State]) -> Result<Mpp, SolverError> {
+        self.try_mpp_with_stats(states, &mut SolverStats::new())
+    }
+
+    pub fn try_mpp_with_stats(&self, states: &[E::State], stats: &mut SolverStats) -> Result<Mpp, SolverError> {
+        let v_oc: f64 = self.try_v_from_i_with_stats(states, 0.0, stats)?;
+        return global_mpp(|v| self.try_i_from_v_with_stats(states, v, stats), v_oc, self.mpp_grid());
+    }
+
+    /// MPP under uniform conditions, together with the states it was computed from.
+    pub fn mpp_at(&self, irrad_ef: f64, cell_temp: f64) -> (Mpp, Vec<E::State>) {
+        let states = self.states_uniform_conditions(irrad_ef, cell_temp);
+        return (self.mpp(&states), states);
+    }
+
+    pub fn iv_curve(&self, states: &[E::State], opts: &CurveOptions) -> IvCurve {
+        let mut stats = SolverStats::new();
+        let isc: f64 = self.i_from_v_lenient(states, 0.0, &mut stats);
+        let voc: f64 = self.v_from_i_lenient(states, 0.0, &mut stats);
+        let mpp: Mpp = self.mpp(states);
+        let Ok(curve) = build_curve(|v| Ok::<f64, Infallible>(self.i_from_v_lenient(states, v, &mut stats)), isc, voc, mpp, opts);
+        return curve;
+    }
+
+    pub fn try_iv_curve(&self, states: &[E::State], opts: &CurveOptions) -> Result<IvCurve, SolverError> {
+        self.try_iv_curve_with_stats(states, opts, &mut SolverStats::new())
+    }
+
+    pub fn try_iv_curve_with_stats(&self, states: &[E::State], opts: &CurveOptions, stats: &mut SolverStats) -> Result<IvCurve, SolverError> {
+        let isc: f64 = self.try_i_from_v_with_stats(states, 0.0, stats)?;
+        let voc: f64 = self.try_v_from_i_with_stats(states, 0.0, stats)?;
+        let mpp: Mpp = self.try_mpp_with_stats(states, stats)?;
+        return build_curve(|v| self.try_i_from_v_with_stats(states, v, stats), isc, voc, mpp, opts);
+    }
+
+}
+
+impl<E: Element> Parallel<Series<E>> {
+    /// Conditions per module: `irrad_ef[string][element]`, `cell_temp[string][element]`.
+    pub fn states_conditions(&self, irrad_ef: &[Vec<f64>], cell_temp: &[Vec<f64>]) -> Vec<Vec<E::State>> {
+        assert_eq!(irrad_ef.len(), self.len(), "Parallel::states_conditions: irradiâncias para cada string");
+        assert_eq!(cell_temp.len(), self.len(), "Parallel::states_conditions: temperaturas para cada string");
+        let mut states: Vec<Vec<E::State>> = Vec::with_capacity(self.len());
+        for (k, string) in self.elements.iter().enumerate(){
+            states.push(string.states_conditions(&irrad_ef[k], &cell_temp[k]));
+        }
+        return states;
+    }
+
+    /// `poa_global[string][element]` and `irrad_ef[string][element]` from weather data, as in [`Series::states_ambient`].
+    pub fn states_ambient(&self, poa_global: &[Vec<f64>], irrad_ef: &[Vec<f64>], ambient: &Ambient, thermal: &ThermalModel) -> Vec<Vec<E::State>> {
+        assert_eq!(poa_global.len(), self.len(), "Parallel::states_ambient: irradiâncias para cada string");
+        assert_eq!(irrad_ef.len(), self.len(), "Parallel::states_ambient: irradiâncias para cada string");
+        let mut states: Vec<Vec<E::State>> = Vec::with_capacity(self.len());
+        for (k, string) in self.elements.iter().enumerate(){
+            states.push(string.states_ambient(&poa_global[k], &irrad_ef[k], ambient, thermal));
+        }
+        return states;
+    }
+
+    /// Uniform conditions with a shading map `shading[string][element]`.
+    pub fn states_shading(&self, irrad_ef: f64, cell_temp: f64, shading: &[Vec<f64>]) -> Vec<Vec<E::State>> {
+        assert_eq!(shading.len(), self.len(), "Parallel::states_shading: sombreamento para cada string");
+        let mut states: Vec<Vec<E::State>> = Vec::with_capacity(self.len());
+        for (k, string) in self.elements.iter().enumerate(){
+            states.push(string.states_shading(irrad_ef, cell_temp, &shading[k]));
+        }
+        return states;
+    }
+}
+
+impl Parallel {
     pub fn find_parallel_equivalent(&self, other: &Series) -> Option<usize> {
         for (k, s) in self.elements.iter().enumerate(){
             if s.is_parallel_equivalent(other) {
@@ -66,45 +389,133 @@ impl Parallel {
         return None;
     }
 
+    /// String level maps only; see [`Parallel::reduce_map`] for the module level.
     pub fn reduce(&self) -> (Parallel, Vec<u32>, Vec<Vec<u32>>) {
-        let mut reduced: Parallel = Parallel::empty();
+        let (reduced, map) = self.reduce_map();
+        return (reduced, map.origin_to_reduced, map.reduced_to_origin);
+    }
+
+    pub fn reduce_map(&self) -> (Parallel, ParallelMap) {
+        let mut reduced: Parallel = Parallel::empty().with_solver(self.solver.clone());
         let mut origin_to_reduced: Vec<u32> = vec![0; self.len()];
         let mut reduced_to_origin: Vec<Vec<u32>> = vec![];
+        let mut elements_origin_to_reduced: Vec<Vec<u32>> = Vec::with_capacity(self.len());
 
-        for i in 0..self.elements.len() {
-
-            let (copy, _, _) = self.elements[i].reduce();
+        for (i, string) in self.elements.iter().enumerate() {
+            let (copy, o_to_r, _) = string.reduce();
             match reduced.find_parallel_equivalent(&copy) {
                 Some(j) => {
                     for (r, c) in reduced.elements[j].elements.iter_mut().zip(copy.iter()) {
                         r.np += c.np;
                     }
-                    // reduced_to_origin[j].push(i as u32);
-                    // origin_to_reduced[i] = j as u32;
+                    reduced_to_origin[j].push(i as u32);
+                    origin_to_reduced[i] = j as u32;
                 }
                 None => {
-                    // origin_to_reduced[i] = reduced.elements.len() as u32;
-                    reduced.elements.push(copy.clone());
-                    // reduced_to_origin.push(vec![i as u32]);
+                    origin_to_reduced[i] = reduced.elements.len() as u32;
+                    reduced.elements.push(copy);
+                    reduced_to_origin.push(vec![i as u32]);
                 }
             }
+            elements_origin_to_reduced.push(o_to_r);
+        }
+
+        // fração da tensão (ns) e da corrente (np) do elemento reduzido que cabe a cada módulo original
+        let mut v_share: Vec<Vec<f64>> = Vec::with_capacity(self.len());
+        let mut i_share: Vec<Vec<f64>> = Vec::with_capacity(self.len());
+        for (i, string) in self.elements.iter().enumerate() {
+            let r_string = &reduced.elements[origin_to_reduced[i] as usize];
+            let mut vs: Vec<f64> = Vec::with_capacity(string.len());
+            let mut is: Vec<f64> = Vec::with_capacity(string.len());
+            for (m, pnl) in string.iter().enumerate() {
+                let r = &r_string[elements_origin_to_reduced[i][m] as usize];
+                vs.push(pnl.ns as f64 / r.ns as f64);
+                is.push(pnl.np as f64 / r.np as f64);
+            }
+            v_share.push(vs);
+            i_share.push(is);
         }
-        return (reduced, origin_to_reduced, reduced_to_origin);
+
+        let map = ParallelMap { origin_to_reduced, reduced_to_origin, elements_origin_to_reduced, v_share, i_share };
+        return (reduced, map);
     }
+}
 
-    pub fn states_uniform_conditions(&self, irrad_ef: f64, cell_temp: f64) -> Vec<Vec<PvCellState>> {
-        let mut states: Vec<Vec<PvCellState>> = Vec::with_capacity(self.len());
-        for string in self.elements.iter(){
-            states.push(string.states_uniform_conditions(irrad_ef, cell_temp));
+impl<E: Element> Element for Parallel<E> {
+    type State = Vec<E::State>;
+
+    fn compute_state(&self, irrad_ef: f64, cell_temp: f64) -> Vec<E::State> {
+        return self.states_uniform_conditions(irrad_ef, cell_temp);
+    }
+
+    fn current_at(&self, states: &Vec<E::State>, v: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if strict {
+            return self.try_i_from_v_with_stats(states, v, stats);
         }
-        return states;
+        return Ok(self.i_from_v_lenient(states, v, stats));
     }
 
-    pub fn i_from_v(&self, states: &Vec<Vec<PvCellState>>, v: f64) -> f64 {
-        let mut i_arr = 0.0;
-        for (k, it) in self.elements.iter().enumerate() {
-            i_arr += it.i_from_v(&states[k], v)
+    fn voltage_at(&self, states: &Vec<E::State>, i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if strict {
+            return self.try_v_from_i_with_stats(states, i, stats);
+        }
+        return Ok(self.v_from_i_lenient(states, i, stats));
+    }
+
+    fn conductance_at(&self, states: &Vec<E::State>, v: f64, _i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let (_, g) = self.i_and_di_dv(states, v, strict, stats)?;
+        return Ok(g);
+    }
+
+    fn nominal_voc(&self) -> f64 {
+        return self.elements.iter().map(|e| e.nominal_voc()).fold(0.0, f64::max);
+    }
+
+    fn nominal_il(&self) -> f64 {
+        return self.elements.iter().map(|e| e.nominal_il()).sum();
+    }
+
+    fn mpp_grid(&self) -> usize {
+        return self.elements.iter().map(|e| e.mpp_grid()).max().unwrap_or(0);
+    }
+
+    fn reduced(&self) -> Self {
+        let mut reduced: Parallel<E> = Parallel::empty().with_solver(self.solver.clone());
+        for e in self.elements.iter() {
+            let e = e.reduced();
+            if !reduced.elements.iter_mut().any(|r| r.merge_parallel(&e)) {
+                reduced.elements.push(e);
+            }
+        }
+        return reduced;
+    }
+
+    /// Element-wise, with the same ratio for every element.
+    fn series_ratio(&self, other: &Self) -> Option<f64> {
+        return common_ratio(&self.elements, &other.elements, |s, o| s.series_ratio(o));
+    }
+
+    /// Element-wise, with the same ratio for every element.
+    fn parallel_ratio(&self, other: &Self) -> Option<f64> {
+        return common_ratio(&self.elements, &other.elements, |s, o| s.parallel_ratio(o));
+    }
+
+    fn merge_series(&mut self, other: &Self) -> bool {
+        if self.series_ratio(other).is_none() {
+            return false;
+        }
+        for (s, o) in self.elements.iter_mut().zip(other.elements.iter()) {
+            s.merge_series(o);
         }
-        return i_arr;
+        return true;
     }
-}
\ No newline at end of file
+
+    fn merge_parallel(&mut self, other: &Self) -> bool {
+        for o in other.elements.iter() {
+            if !self.elements.iter_mut().any(|s| s.merge_parallel(o)) {
+                self.elements.push(o.clone());
+            }
+        }
+        return true;
+    }
+}
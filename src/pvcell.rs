@@ -1,38 +1,141 @@
 
 
 
-const T_REF: f64 = 298.15; // [K] rated temperature
-const S_REF: f64 = 1000.0; // [W/m^2] rated irradiance
-const Q_K: f64 = 1.60217663e-19 / 1.38064852e-23;  // [K/eV] Boltzmann constante reciprocal
-const C_TO_K: f64 = 273.15;
+pub(crate) const T_REF: f64 = 298.15; // [K] rated temperature
+pub(crate) const S_REF: f64 = 1000.0; // [W/m^2] rated irradiance
+pub(crate) const Q_K: f64 = 1.60217663e-19 / 1.38064852e-23;  // [K/eV] Boltzmann constante reciprocal
+pub(crate) const C_TO_K: f64 = 273.15;
+const MPP_GRID: usize = 20;  // intervals of the P-V sweep of a single element
 
 use std::fmt;
 use tracing::{warn, error};
+use crate::error::{FitError, OperatingPoint, SolverError};
+use crate::fit::{Datasheet, fit_datasheet};
+use crate::stats::SolverStats;
+use crate::lambertw::lambert_w0_exp;
+use crate::mpp::{Mpp, global_mpp};
+use crate::small_signal::SmallSignal;
+use crate::curve::{CurveOptions, IvCurve, build_curve};
+use crate::roots::{Root, brent, bracket_decreasing, newton_bracketed};
+use crate::element::Element;
+use std::convert::Infallible;
 
-pub static mut SOLVER_CALLS: usize = 0;
-
+/// Method used to solve the implicit diode equation. `LambertW` has no closed form for the
+/// two-diode model and falls back to `Newton` when the recombination diode is present.
+/// With a [`Breakdown`] term both methods are replaced by a bracketed search on the junction voltage.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PvCellMethod {
+    #[default]
+    Newton,     // Newton-Raphson with fixed starting point
+    LambertW,   // explicit solution through the Lambert W function (no iteration)
+}
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PvCellSolver {
     pub max_iter: usize,    // max number of iterations
     pub tol_i: f64, // [A] current tolerance
     pub tol_v: f64, // [V] voltage tolerance
+    pub method: PvCellMethod,
 }
 
 impl Default for PvCellSolver {
     fn default() -> Self {
-        return PvCellSolver { max_iter: 100, tol_i: 0.001, tol_v: 0.01 };
+        return PvCellSolver { max_iter: 100, tol_i: 0.001, tol_v: 0.01, method: PvCellMethod::Newton };
+    }
+}
+
+/// Outcome of a junction-level solve, before bypass and `ns`/`np` scaling.
+struct JunctionSolve {
+    value: f64,
+    iterations: usize,
+    residual: f64,
+    converged: bool,
+}
+
+/// Bishop reverse-bias breakdown: the shunt current becomes
+/// `Vj*Gsh * (1 + a * (1 - Vj/v_br)^(-m))`, diverging as `Vj` approaches `v_br`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Breakdown {
+    pub v_br: f64,  // [V] breakdown voltage (negative), at the same level as `v_oc_ref`
+    pub m: f64,     // [-] avalanche exponent (~3.7)
+    pub a: f64,     // [-] fraction of ohmic current in the avalanche term (~0.1)
+}
+
+/// Shockley bypass diode with series resistance. `i_s` is given at the temperature `temp`;
+/// [`BypassDiode::at_temperature`] translates it with the SPICE `XTI`/`EG` law (single junction).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BypassDiode {
+    pub i_s: f64,   // [A]   saturation current
+    pub n: f64,     // [-]   ideality factor
+    pub r_s: f64,   // [Ohm] series resistance
+    pub eg: f64,    // [eV]  activation energy (Schottky: 0.69, Si p-n: 1.11)
+    pub xti: f64,   // [-]   saturation current temperature exponent (Schottky: 2, Si p-n: 3)
+    pub temp: f64,  // [K]   diode temperature
+}
+
+/// Schottky diode: 0.45 V at 10 A and 25 °C.
+impl Default for BypassDiode {
+    fn default() -> Self {
+        BypassDiode::from_forward_voltage(0.45, 10.0, 1.0, 0.005)
     }
 }
 
-#[derive(Clone, Debug)]
+impl BypassDiode {
+    /// Schottky diode with forward voltage `v_f` at the current `i_f` and 25 °C.
+    pub fn from_forward_voltage(v_f: f64, i_f: f64, n: f64, r_s: f64) -> Self {
+        let nvt: f64 = n * T_REF / Q_K;
+        let i_s: f64 = i_f / ((v_f - i_f * r_s) / nvt).exp_m1();
+        return BypassDiode { i_s, n, r_s, eg: 0.69, xti: 2.0, temp: T_REF };
+    }
+
+    /// The same diode at `temp_c` [°C].
+    pub fn at_temperature(&self, temp_c: f64) -> Self {
+        let t: f64 = temp_c + C_TO_K;
+        let ratio: f64 = t / self.temp;
+        let i_s: f64 = self.i_s * ratio.powf(self.xti / self.n) * ((ratio - 1.0) * self.eg * Q_K / (self.n * t)).exp();
+        return BypassDiode { i_s, temp: t, ..*self };
+    }
+
+    fn nvt(&self) -> f64 {
+        return self.n * self.temp / Q_K;
+    }
+
+    /// Forward current [A] at the forward voltage `v_d` (explicit through the Lambert W function).
+    pub fn i_from_v(&self, v_d: f64) -> f64 {
+        let nvt: f64 = self.nvt();
+        if self.r_s == 0.0 {
+            return self.i_s * (v_d / nvt).exp_m1();
+        }
+        let theta: f64 = (self.r_s * self.i_s / nvt).ln() + (v_d + self.r_s * self.i_s) / nvt;
+        return nvt / self.r_s * lambert_w0_exp(theta) - self.i_s;
+    }
+
+    /// Forward voltage [V] at the forward current `i_d` (`-inf` for `i_d <= -i_s`).
+    pub fn v_from_i(&self, i_d: f64) -> f64 {
+        if i_d <= -self.i_s {
+            return f64::NEG_INFINITY;
+        }
+        return self.nvt() * (i_d / self.i_s).ln_1p() + i_d * self.r_s;
+    }
+
+    /// Small-signal conductance [S] at the forward current `i_d`.
+    pub fn conductance(&self, i_d: f64) -> f64 {
+        return 1.0 / (self.r_s + self.nvt() / (i_d + self.i_s));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct PvCellState {
     pub gsh: f64,
     pub ra: f64,
     pub i0: f64,
     pub il: f64,
+    pub i02: f64,   // [A]   recombination diode saturation current (0: single-diode model)
+    pub ra2: f64,   // [1/V] recombination diode 1/a
+    pub bypass: Option<BypassDiode>,    // bypass diode at the cell temperature
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct BasicParams {
     pub a_ref: f64,
     pub i_o_ref: f64,
@@ -52,8 +155,12 @@ pub struct PvCell {
     pub r_sh_ref: f64,
     pub alpha_sc: f64,
     pub v_oc_ref: f64,
+    pub i_o2_ref: f64, // [A] recombination diode saturation current (0: single-diode model)
+    pub a2_ref: f64,   // [V] recombination diode modified ideality factor
     pub v_bypass: f64, // [V] tensão de bypass
     pub r_bypass: f64, // [Ohm] resistance of bypass diode
+    pub bypass_diode: Option<BypassDiode>, // Shockley bypass, replaces v_bypass/r_bypass when set
+    pub breakdown: Option<Breakdown>,
     pub eg_ref: f64,   // [eV]  band energy Si: 1.121, CdTe: 1.475
     pub degdt: f64,    // Si: -0.0002677, CdTe: -0.0003 //
     pub shading: f64,
@@ -62,8 +169,10 @@ pub struct PvCell {
     pub solver: PvCellSolver,
 }
 
-impl PvCell {
-    pub(crate) fn default() -> PvCell {
+/// Extended parameters with the basic (single-diode) parameters left as `NaN`;
+/// use with struct update syntax: `PvCell { a_ref: 1.8, ..PvCell::default() }`.
+impl Default for PvCell {
+    fn default() -> PvCell {
         PvCell {
             a_ref: f64::NAN,
             i_o_ref: f64::NAN,
@@ -72,8 +181,12 @@ impl PvCell {
             r_sh_ref: f64::NAN,
             alpha_sc: f64::NAN,
             v_oc_ref: f64::NAN,
+            i_o2_ref: 0.0,
+            a2_ref: 0.0,
             v_bypass: -0.65 * 3.0,
             r_bypass: 0.1,
+            bypass_diode: None,
+            breakdown: None,
             eg_ref: 1.121,
             degdt: -0.0002677,
             shading: 0.0,
@@ -84,7 +197,6 @@ impl PvCell {
     }
 }
 
-#[allow(dead_code)]
 impl PvCell {
     pub fn new(params: &BasicParams) -> Self {
         PvCell {
@@ -94,11 +206,51 @@ impl PvCell {
         }
     }
 
+    /// Cell with the basic parameters fitted to `datasheet` (see [`fit_datasheet`]).
+    pub fn from_datasheet(datasheet: &Datasheet) -> Result<Self, FitError> {
+        let fit = fit_datasheet(datasheet)?;
+        return Ok(PvCell::new(&fit.params));
+    }
+
     /// builders
     pub fn with_ns(mut self, ns: u32) -> Self{ self.ns = ns; return self; }
     pub fn with_np(mut self, np: u32) -> Self{ self.np = np; return self; }
     pub fn with_shading(mut self, shading: f64) -> Self{ self.shading = shading; return self; }
     pub fn with_solver(mut self, settings: PvCellSolver) -> Self { self.solver = settings; return self; }
+    pub fn with_breakdown(mut self, breakdown: Breakdown) -> Self { self.breakdown = Some(breakdown); return self; }
+    /// Removes the bypass diode: in reverse bias only the cell itself conducts.
+    pub fn without_bypass(mut self) -> Self { self.v_bypass = f64::NEG_INFINITY; self.bypass_diode = None; return self; }
+    /// Exponential bypass diode (per element of `ns`), smooth through activation.
+    pub fn with_bypass_diode(mut self, diode: BypassDiode) -> Self { self.bypass_diode = Some(diode); return self; }
+    /// One of `n` identical cells in series making up this element (voltages divided by `n`),
+    /// with the bypass removed.
+    pub fn split_series(&self, n: u32) -> PvCell {
+        let k: f64 = n as f64;
+        return PvCell {
+            a_ref: self.a_ref / k, r_s: self.r_s / k, r_sh_ref: self.r_sh_ref / k, v_oc_ref: self.v_oc_ref / k,
+            a2_ref: self.a2_ref / k,
+            breakdown: self.breakdown.map(|br| Breakdown { v_br: br.v_br / k, ..br }),
+            ..self.clone()
+        }.without_bypass();
+    }
+
+    /// One of `n` identical cells in parallel making up this element (currents divided by `n`),
+    /// e.g. `split_parallel(2)` for the half cells of a half-cut module.
+    pub fn split_parallel(&self, n: u32) -> PvCell {
+        let k: f64 = n as f64;
+        return PvCell {
+            i_l_ref: self.i_l_ref / k, i_o_ref: self.i_o_ref / k, i_o2_ref: self.i_o2_ref / k, alpha_sc: self.alpha_sc / k,
+            r_s: self.r_s * k, r_sh_ref: self.r_sh_ref * k,
+            ..self.clone()
+        };
+    }
+
+    /// Two-diode model: adds a recombination diode in parallel with the diffusion diode.
+    pub fn with_recombination_diode(mut self, i_o2_ref: f64, a2_ref: f64) -> Self {
+        self.i_o2_ref = i_o2_ref;
+        self.a2_ref = a2_ref;
+        return self;
+    }
 
     pub fn compute_state(&self, irrad_ef: f64, cell_temp: f64) -> PvCellState {
         let irrad: f64 = irrad_ef * (1.0 - self.shading);
@@ -108,72 +260,381 @@ impl PvCell {
         let ra: f64 = T_REF / (self.a_ref * tj);
         let i0: f64 = self.i_o_ref  * (tj / T_REF).powi(3) * (Q_K * (self.eg_ref / T_REF - eg / tj)).exp();
         let il: f64 = (self.i_l_ref + self.alpha_sc * (tj - T_REF)) * irrad / S_REF;
-        PvCellState {gsh, ra, i0, il}
+        // recombinação: I02 ~ T^(5/2) * exp(-Eg/(2kT))
+        let (i02, ra2) = if self.i_o2_ref == 0.0 { (0.0, 0.0) } else {
+            (self.i_o2_ref * (tj / T_REF).powf(2.5) * (0.5 * Q_K * (self.eg_ref / T_REF - eg / tj)).exp(),
+             T_REF / (self.a2_ref * tj))
+        };
+        let bypass = self.bypass_diode.map(|d| d.at_temperature(cell_temp));
+        PvCellState {gsh, ra, i0, il, i02, ra2, bypass}
+    }
+
+    /// Inverse of `compute_state`: reference parameters that reproduce `state` at the given
+    /// conditions. `r_s` is not part of the state and is taken as given; the temperature and
+    /// band-gap coefficients, bypass and `ns`/`np` are kept.
+    pub fn with_state(mut self, state: &PvCellState, r_s: f64, irrad_ef: f64, cell_temp: f64) -> Self {
+        let irrad: f64 = irrad_ef * (1.0 - self.shading);
+        let tj: f64 = cell_temp + C_TO_K;
+        let eg: f64 = self.eg_ref * (1. + self.degdt  * (tj - T_REF));
+        self.a_ref = T_REF / (state.ra * tj);
+        self.r_sh_ref = irrad / (state.gsh * S_REF);
+        self.i_o_ref = state.i0 / ((tj / T_REF).powi(3) * (Q_K * (self.eg_ref / T_REF - eg / tj)).exp());
+        self.i_l_ref = state.il * S_REF / irrad - self.alpha_sc * (tj - T_REF);
+        self.r_s = r_s;
+        self.i_o2_ref = state.i02 / ((tj / T_REF).powf(2.5) * (0.5 * Q_K * (self.eg_ref / T_REF - eg / tj)).exp());
+        self.a2_ref = if state.i02 == 0.0 { 0.0 } else { T_REF / (state.ra2 * tj) };
+        return self;
     }
 
     pub fn solve_i(&self, state: &PvCellState, v_pnl: f64) -> f64 {
-        unsafe{ SOLVER_CALLS += 1}
-        let mut i: f64 = 0.0;
-        let v: f64 = v_pnl / (self.ns as f64);
+        self.solve_i_lenient(state, v_pnl, &mut SolverStats::new())
+    }
 
-        let mut success: bool = false;
-        for _ in 0..self.solver.max_iter {
-            let den: f64 = -1.0 - state.i0 * ((v + i * self.r_s) * state.ra).exp() * self.r_s * state.ra - self.r_s * state.gsh;
-            let d: f64 = (state.il - i - state.i0 * (((v + i * self.r_s) * state.ra).exp() - 1.0) - (v + i * self.r_s) * state.gsh) / den;
-            i = i - d;
-            if d.abs() < self.solver.tol_i {
-                success = true;
-                break;
+    /// Lenient, accumulating the solver statistics in `stats`.
+    pub fn solve_i_with_stats(&self, state: &PvCellState, v_pnl: f64, stats: &mut SolverStats) -> f64 {
+        self.solve_i_lenient(state, v_pnl, stats)
+    }
+
+    pub fn try_solve_i(&self, state: &PvCellState, v_pnl: f64) -> Result<f64, SolverError> {
+        self.try_solve_i_with_stats(state, v_pnl, &mut SolverStats::new())
+    }
+
+    /// Logs a non-converged solve and returns its last iterate.
+    pub(crate) fn solve_i_lenient(&self, state: &PvCellState, v_pnl: f64, stats: &mut SolverStats) -> f64 {
+        match self.try_solve_i_with_stats(state, v_pnl, stats) {
+            Ok(i) => i,
+            Err(e) => {
+                let i = e.last_value();
+                if i.is_normal() {
+                    warn!("({:p}) PvCell::solve_i(v_pnl={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
+                        &self, v_pnl, self.solver.tol_i, self.solver.max_iter, i);
+                }else{
+                    error!("({:p}) PvCell::solve_i(v_pnl={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
+                        &self, v_pnl, self.solver.tol_i, self.solver.max_iter, i);
+                }
+                i
             }
         }
-        if v < self.v_bypass {
+    }
+
+    pub fn try_solve_i_with_stats(&self, state: &PvCellState, v_pnl: f64, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let v: f64 = v_pnl / (self.ns as f64);
+
+        let sol = match self.solver.method {
+            _ if self.breakdown.is_some() => self.bracketed_i(state, v),
+            PvCellMethod::LambertW if state.i02 == 0.0 => self.lambert_i(state, v),
+            _ => self.newton_i(state, v),
+        };
+        stats.cell.record(sol.iterations, sol.residual, sol.converged);
+
+        let mut i: f64 = sol.value;
+        if let Some(d) = &state.bypass {
+            i += d.i_from_v(-v);
+        } else if v < self.v_bypass {
             i += (self.v_bypass - v) / self.r_bypass;
         }
         i *= self.np as f64;
 
-        if !success {
-            if i.is_normal() {
-                warn!("({:p}) PvCell::solve_i(v_pnl={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
-                    &self, v_pnl, self.solver.tol_i, self.solver.max_iter, i);
-            }else{
-                error!("({:p}) PvCell::solve_i(v_pnl={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
-                    &self, v_pnl, self.solver.tol_i, self.solver.max_iter, i);
-            }
+        if !sol.converged {
+            return Err(SolverError::not_converged(sol.iterations, sol.residual, self.solver.tol_i, OperatingPoint::Voltage(v_pnl), i));
         }
-        return i;
+        return Ok(i);
     }
 
     pub fn v_from_i(&self, state: &PvCellState, i_pnl: f64) -> f64 {
-        unsafe{ SOLVER_CALLS += 1}
-        let mut v: f64 = self.v_oc_ref;
-        let i: f64 = i_pnl / (self.np as f64);
+        self.v_from_i_lenient(state, i_pnl, &mut SolverStats::new())
+    }
 
-        let mut success = false;
-        for _ in 0..self.solver.max_iter {
-            let den: f64 = -state.i0 * ((v + i * self.r_s) * state.ra).exp() * state.ra - (state.gsh);
-            let d: f64 = (state.il - i - state.i0 * (((v + i * self.r_s) * state.ra).exp() - 1.) - (v + i * self.r_s) * state.gsh) / den;
-            v -= d;
-            if d.abs() < self.solver.tol_v {
-                success = true;
-                break;
+    /// Lenient, accumulating the solver statistics in `stats`.
+    pub fn v_from_i_with_stats(&self, state: &PvCellState, i_pnl: f64, stats: &mut SolverStats) -> f64 {
+        self.v_from_i_lenient(state, i_pnl, stats)
+    }
+
+    pub fn try_v_from_i(&self, state: &PvCellState, i_pnl: f64) -> Result<f64, SolverError> {
+        self.try_v_from_i_with_stats(state, i_pnl, &mut SolverStats::new())
+    }
+
+    pub fn try_v_from_i_with_stats(&self, state: &PvCellState, i_pnl: f64, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        self.v_from_i_impl(state, i_pnl, true, stats)
+    }
+
+    /// Logs a non-converged solve and returns its last iterate.
+    pub(crate) fn v_from_i_lenient(&self, state: &PvCellState, i_pnl: f64, stats: &mut SolverStats) -> f64 {
+        match self.v_from_i_impl(state, i_pnl, false, stats) {
+            Ok(v) => v,
+            Err(e) => {
+                let v = e.last_value();
+                if v.is_normal() {
+                    warn!("({:p}) PvCell::v_from_i(i_pnl={:e}) nao convergiu (tol={:e}, max_iter={}) -> (v={})",
+                        &self, i_pnl, self.solver.tol_v, self.solver.max_iter, v);
+                }else{
+                    error!("({:p}) PvCell::v_from_i(i_pnl={:e}) nao convergiu (tol={:e}, max_iter={}) -> (v={})",
+                        &self, i_pnl, self.solver.tol_v, self.solver.max_iter, v);
+                }
+                v
             }
         }
-        if v < self.v_bypass {
-            let ir: f64 = self.solve_i(state, self.v_bypass) / (self.np as f64);   // corrente em que se inicia região de breakdown para solve_v(i)
+    }
+
+    /// `strict`: propagate failures of the nested `solve_i` instead of using its last iterate
+    fn v_from_i_impl(&self, state: &PvCellState, i_pnl: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let i: f64 = i_pnl / (self.np as f64);
+
+        let sol = match self.solver.method {
+            _ if state.bypass.is_some() => self.bypass_v(state, i),
+            _ if self.breakdown.is_some() => self.bracketed_v(state, i),
+            PvCellMethod::LambertW if state.i02 == 0.0 => self.lambert_v(state, i),
+            _ => self.newton_v(state, i),
+        };
+        stats.cell.record(sol.iterations, sol.residual, sol.converged);
+
+        let mut v: f64 = sol.value;
+        if state.bypass.is_none() && v < self.v_bypass {
+            // corrente em que se inicia região de breakdown para solve_v(i)
+            let ir: f64 = if strict {
+                self.try_solve_i_with_stats(state, self.v_bypass, stats)?
+            } else {
+                self.solve_i_lenient(state, self.v_bypass, stats)
+            } / (self.np as f64);
             v = self.v_bypass - (i - ir) * self.r_bypass;
         }
         v *=  self.ns as f64;
 
-        if !success {
-            if v.is_normal() {
-                warn!("({:p}) PvCell::v_from_i(i_pnl={:e}) nao convergiu (tol={:e}, max_iter={}) -> (v={})",
-                    &self, i_pnl, self.solver.tol_v, self.solver.max_iter, v);
-            }else{
-                error!("({:p}) PvCell::v_from_i(i_pnl={:e}) nao convergiu (tol={:e}, max_iter={}) -> (v={})",
-                    &self, i_pnl, self.solver.tol_v, self.solver.max_iter, v);
+        if !sol.converged || !v.is_finite() {
+            return Err(SolverError::not_converged(sol.iterations, sol.residual, self.solver.tol_v, OperatingPoint::Current(i_pnl), v));
+        }
+        return Ok(v);
+    }
+
+    /// Residual [A] of the diode equation at junction level.
+    fn residual(&self, state: &PvCellState, v: f64, i: f64) -> f64 {
+        let vj: f64 = v + i * self.r_s;
+        return self.junction_current(state, vj) - i;
+    }
+
+    /// Current [A] delivered by the junction (photocurrent minus diodes and shunt) at junction voltage `vj`.
+    fn junction_current(&self, state: &PvCellState, vj: f64) -> f64 {
+        let mut i_sh: f64 = vj * state.gsh;
+        if let Some(br) = &self.breakdown {
+            i_sh *= 1.0 + br.a * (1.0 - vj / br.v_br).powf(-br.m);
+        }
+        return state.il - state.i0 * ((vj * state.ra).exp() - 1.0) - recombination(state, vj).0 - i_sh;
+    }
+
+    /// dI/dV [S] at the terminal operating point `(v_pnl, i_pnl)` (e.g. from `solve_i` or
+    /// `v_from_i`), bypass branch included.
+    pub fn conductance(&self, state: &PvCellState, v_pnl: f64, i_pnl: f64) -> f64 {
+        let v: f64 = v_pnl / (self.ns as f64);
+        let i: f64 = i_pnl / (self.np as f64);
+        let (i_b, g_b): (f64, f64) = match &state.bypass {
+            Some(d) => {
+                let i_b: f64 = d.i_from_v(-v);
+                (i_b, d.conductance(i_b))
+            }
+            None if v < self.v_bypass => ((self.v_bypass - v) / self.r_bypass, 1.0 / self.r_bypass),
+            None => (0.0, 0.0),
+        };
+        let vj: f64 = v + (i - i_b) * self.r_s;
+        let g_j: f64 = self.junction_conductance(state, vj);
+        return -(g_j / (1.0 + g_j * self.r_s) + g_b) * (self.np as f64) / (self.ns as f64);
+    }
+
+    pub fn small_signal(&self, state: &PvCellState, v_pnl: f64) -> SmallSignal {
+        let i: f64 = self.solve_i(state, v_pnl);
+        return SmallSignal { v: v_pnl, i, g: self.conductance(state, v_pnl, i) };
+    }
+
+    pub fn try_small_signal(&self, state: &PvCellState, v_pnl: f64) -> Result<SmallSignal, SolverError> {
+        self.try_small_signal_with_stats(state, v_pnl, &mut SolverStats::new())
+    }
+
+    pub fn try_small_signal_with_stats(&self, state: &PvCellState, v_pnl: f64, stats: &mut SolverStats) -> Result<SmallSignal, SolverError> {
+        let i: f64 = self.try_solve_i_with_stats(state, v_pnl, stats)?;
+        return Ok(SmallSignal { v: v_pnl, i, g: self.conductance(state, v_pnl, i) });
+    }
+
+    /// Small-signal conductance [S] of the junction (diodes and shunt) at junction voltage `vj`.
+    fn junction_conductance(&self, state: &PvCellState, vj: f64) -> f64 {
+        let mut g_sh: f64 = state.gsh;
+        if let Some(br) = &self.breakdown {
+            let x: f64 = 1.0 - vj / br.v_br;
+            g_sh *= 1.0 + br.a * x.powf(-br.m) + br.a * br.m * (vj / br.v_br) * x.powf(-br.m - 1.0);
+        }
+        return state.i0 * state.ra * (vj * state.ra).exp() + recombination(state, vj).1 + g_sh;
+    }
+
+    /// Voltage with the Shockley bypass diode across the element: the cell carries `i - i_b(v)`,
+    /// and the combined residual is solved by a bracketed Newton on `v`.
+    fn bypass_v(&self, state: &PvCellState, i: f64) -> JunctionSolve {
+        let Some(d) = state.bypass else {
+            return self.newton_v(state, i);
+        };
+        let f = |v: f64| -> (f64, f64) {
+            let i_b: f64 = d.i_from_v(-v);
+            let g_b: f64 = d.conductance(i_b);
+            let i_c: f64 = i - i_b;
+            let vj: f64 = v + i_c * self.r_s;
+            (self.junction_current(state, vj) - i_c, -self.junction_conductance(state, vj) * (1.0 + g_b * self.r_s) - g_b)
+        };
+        // em lo o bypass conduz mais que i
+        let lo: f64 = -d.v_from_i(i.max(0.0)) - 0.1;
+        let hi: f64 = self.v_oc_guess().max(lo + 1.0);
+        let Ok(bracket) = bracket_decreasing(|v| Ok::<f64, Infallible>(f(v).0), lo, hi, 60);
+        let Some((lo, hi, _, _)) = bracket else {
+            return JunctionSolve { value: f64::NAN, iterations: 0, residual: f64::NAN, converged: false };
+        };
+        let Ok(root) = newton_bracketed(|v| Ok::<(f64, f64), Infallible>(f(v)), lo, hi, self.v_oc_guess(), self.solver.tol_v, 0.0, self.solver.max_iter);
+        let (fx, dfx) = f(root.x);
+        return JunctionSolve { value: root.x, iterations: root.iterations, residual: (fx / dfx).abs(), converged: root.converged };
+    }
+
+    /// Brackets the junction voltage between `v_br` (where the junction current diverges) and the
+    /// first voltage where `excess(vj) <= 0`, then refines it with Brent's method.
+    fn bracketed_junction(&self, mut excess: impl FnMut(f64) -> f64, tol: f64) -> (f64, Root) {
+        let mut f = |vj: f64| Ok::<f64, Infallible>(excess(vj));
+        let v_br: f64 = self.breakdown.map_or(f64::NEG_INFINITY, |br| br.v_br);
+        let lo: f64 = v_br * (1.0 - 1e-9);
+        let hi: f64 = lo.max(0.0) + 1.0;
+        let Ok(bracket) = bracket_decreasing(&mut f, lo, hi, 60);
+        let Some((lo, hi, f_lo, f_hi)) = bracket else {
+            return (f64::NAN, Root { x: f64::NAN, fx: f64::NAN, iterations: 0, converged: false });
+        };
+        let Ok(root) = brent(&mut f, lo, hi, f_lo, f_hi, tol, self.solver.max_iter);
+        return (root.x, root);
+    }
+
+    fn bracketed_i(&self, state: &PvCellState, v: f64) -> JunctionSolve {
+        if self.r_s == 0.0 {
+            let i: f64 = self.junction_current(state, v);
+            return JunctionSolve { value: i, iterations: 0, residual: 0.0, converged: i.is_finite() };
+        }
+        let (vj, root) = self.bracketed_junction(|vj| self.junction_current(state, vj) - (vj - v) / self.r_s, self.solver.tol_i);
+        let i: f64 = (vj - v) / self.r_s;
+        return JunctionSolve { value: i, iterations: root.iterations, residual: root.fx.abs(), converged: root.converged };
+    }
+
+    fn bracketed_v(&self, state: &PvCellState, i: f64) -> JunctionSolve {
+        let (vj, root) = self.bracketed_junction(|vj| self.junction_current(state, vj) - i, self.solver.tol_i);
+        let v: f64 = vj - i * self.r_s;
+        let residual: f64 = if v.is_finite() { self.residual(state, v, i).abs() } else { f64::NAN };
+        return JunctionSolve { value: v, iterations: root.iterations, residual, converged: root.converged };
+    }
+
+    fn newton_i(&self, state: &PvCellState, v: f64) -> JunctionSolve {
+        let mut i: f64 = 0.0;
+        let mut iterations: usize = 0;
+        let mut d: f64 = f64::NAN;
+        for _ in 0..self.solver.max_iter {
+            iterations += 1;
+            let (i2, g2) = recombination(state, v + i * self.r_s);
+            let den: f64 = -1.0 - state.i0 * ((v + i * self.r_s) * state.ra).exp() * self.r_s * state.ra - self.r_s * state.gsh - g2 * self.r_s;
+            d = (state.il - i - state.i0 * (((v + i * self.r_s) * state.ra).exp() - 1.0) - (v + i * self.r_s) * state.gsh - i2) / den;
+            i -= d;
+            if d.abs() < self.solver.tol_i {
+                return JunctionSolve { value: i, iterations, residual: d.abs(), converged: true };
             }
         }
-        return v;
+        return JunctionSolve { value: i, iterations, residual: d.abs(), converged: false };
+    }
+
+    /// Per-unit open-circuit voltage [V] at reference conditions: `v_oc_ref`, or without it
+    /// the single-diode estimate `a_ref ln(1 + I_L/I_0)`, shunt neglected. `NaN` when the basic
+    /// parameters are not set either (`PvCell::default()`); the solves then report not converged.
+    fn v_oc_guess(&self) -> f64 {
+        if !self.v_oc_ref.is_nan() {
+            return self.v_oc_ref;
+        }
+        return self.a_ref * (self.i_l_ref / self.i_o_ref).ln_1p();
+    }
+
+    fn newton_v(&self, state: &PvCellState, i: f64) -> JunctionSolve {
+        let mut v: f64 = self.v_oc_guess();
+        let mut iterations: usize = 0;
+        let mut d: f64 = f64::NAN;
+        for _ in 0..self.solver.max_iter {
+            iterations += 1;
+            let (i2, g2) = recombination(state, v + i * self.r_s);
+            let den: f64 = -state.i0 * ((v + i * self.r_s) * state.ra).exp() * state.ra - (state.gsh) - g2;
+            d = (state.il - i - state.i0 * (((v + i * self.r_s) * state.ra).exp() - 1.) - (v + i * self.r_s) * state.gsh - i2) / den;
+            v -= d;
+            if d.abs() < self.solver.tol_v {
+                return JunctionSolve { value: v, iterations, residual: d.abs(), converged: true };
+            }
+        }
+        return JunctionSolve { value: v, iterations, residual: d.abs(), converged: false };
+    }
+
+    /// I(V) = (IL + I0 - V*Gsh)/(1 + Rs*Gsh) - a/Rs * W(Rs*I0/(a*(1 + Rs*Gsh)) * exp((Rs*(IL + I0) + V)/(a*(1 + Rs*Gsh))))
+    fn lambert_i(&self, state: &PvCellState, v: f64) -> JunctionSolve {
+        let i: f64 = if self.r_s == 0.0 {
+            state.il - state.i0 * ((v * state.ra).exp() - 1.0) - v * state.gsh
+        } else {
+            let a: f64 = 1.0 / state.ra;
+            let den: f64 = 1.0 + self.r_s * state.gsh;
+            let theta: f64 = (self.r_s * state.i0 / (a * den)).ln() + (self.r_s * (state.il + state.i0) + v) / (a * den);
+            (state.il + state.i0 - v * state.gsh) / den - a / self.r_s * lambert_w0_exp(theta)
+        };
+        let residual: f64 = self.residual(state, v, i).abs();
+        return JunctionSolve { value: i, iterations: 0, residual, converged: i.is_finite() };
+    }
+
+    /// V(I) = (IL + I0 - I)/Gsh - I*Rs - a * W(I0/(a*Gsh) * exp((IL + I0 - I)/(a*Gsh)))
+    fn lambert_v(&self, state: &PvCellState, i: f64) -> JunctionSolve {
+        let a: f64 = 1.0 / state.ra;
+        let v: f64 = if state.gsh == 0.0 {
+            if state.il + state.i0 - i > 0.0 {
+                a * ((state.il + state.i0 - i) / state.i0).ln() - i * self.r_s
+            } else {
+                f64::NEG_INFINITY  // sem shunt não há condução reversa: só o bypass conduz
+            }
+        } else {
+            let theta: f64 = (state.i0 / (a * state.gsh)).ln() + (state.il + state.i0 - i) / (a * state.gsh);
+            (state.il + state.i0 - i) / state.gsh - i * self.r_s - a * lambert_w0_exp(theta)
+        };
+        let residual: f64 = if v.is_finite() { self.residual(state, v, i).abs() } else { 0.0 };
+        return JunctionSolve { value: v, iterations: 0, residual, converged: !v.is_nan() };
+    }
+
+    pub fn mpp(&self, state: &PvCellState) -> Mpp {
+        let mut stats = SolverStats::new();
+        let v_oc: f64 = self.v_from_i_lenient(state, 0.0, &mut stats);
+        let Ok(mpp) = global_mpp(|v| Ok::<f64, Infallible>(self.solve_i_lenient(state, v, &mut stats)), v_oc, MPP_GRID);
+        return mpp;
+    }
+
+    pub fn try_mpp(&self, state: &PvCellState) -> Result<Mpp, SolverError> {
+        self.try_mpp_with_stats(state, &mut SolverStats::new())
+    }
+
+    pub fn try_mpp_with_stats(&self, state: &PvCellState, stats: &mut SolverStats) -> Result<Mpp, SolverError> {
+        let v_oc: f64 = self.try_v_from_i_with_stats(state, 0.0, stats)?;
+        return global_mpp(|v| self.try_solve_i_with_stats(state, v, stats), v_oc, MPP_GRID);
+    }
+
+    /// MPP under the given conditions, together with the state it was computed from.
+    pub fn mpp_at(&self, irrad_ef: f64, cell_temp: f64) -> (Mpp, PvCellState) {
+        let state = self.compute_state(irrad_ef, cell_temp);
+        return (self.mpp(&state), state);
+    }
+
+    pub fn iv_curve(&self, state: &PvCellState, opts: &CurveOptions) -> IvCurve {
+        let mut stats = SolverStats::new();
+        let isc: f64 = self.solve_i_lenient(state, 0.0, &mut stats);
+        let voc: f64 = self.v_from_i_lenient(state, 0.0, &mut stats);
+        let mpp: Mpp = self.mpp(state);
+        let Ok(curve) = build_curve(|v| Ok::<f64, Infallible>(self.solve_i_lenient(state, v, &mut stats)), isc, voc, mpp, opts);
+        return curve;
+    }
+
+    pub fn try_iv_curve(&self, state: &PvCellState, opts: &CurveOptions) -> Result<IvCurve, SolverError> {
+        self.try_iv_curve_with_stats(state, opts, &mut SolverStats::new())
+    }
+
+    pub fn try_iv_curve_with_stats(&self, state: &PvCellState, opts: &CurveOptions, stats: &mut SolverStats) -> Result<IvCurve, SolverError> {
+        let isc: f64 = self.try_solve_i_with_stats(state, 0.0, stats)?;
+        let voc: f64 = self.try_v_from_i_with_stats(state, 0.0, stats)?;
+        let mpp: Mpp = self.try_mpp_with_stats(state, stats)?;
+        return build_curve(|v| self.try_solve_i_with_stats(state, v, stats), isc, voc, mpp, opts);
     }
 
     pub fn is_extended_params_equivalent(&self, other: &PvCell) -> bool {
@@ -184,8 +645,12 @@ impl PvCell {
         self.r_sh_ref == other.r_sh_ref && 
         self.alpha_sc == other.alpha_sc && 
         self.v_oc_ref == other.v_oc_ref && 
+        self.i_o2_ref == other.i_o2_ref && 
+        self.a2_ref == other.a2_ref && 
         self.v_bypass == other.v_bypass && 
         self.r_bypass == other.r_bypass && 
+        self.bypass_diode == other.bypass_diode && 
+        self.breakdown == other.breakdown && 
         self.eg_ref == other.eg_ref && 
         self.degdt == other.degdt && 
         self.shading == other.shading
@@ -200,9 +665,89 @@ impl PvCell {
     }
 }
 
+/// Current [A] and conductance [S] of the recombination diode at junction voltage `vj`.
+fn recombination(state: &PvCellState, vj: f64) -> (f64, f64) {
+    if state.i02 == 0.0 {
+        return (0.0, 0.0);
+    }
+    let e: f64 = (vj * state.ra2).exp();
+    return (state.i02 * (e - 1.0), state.i02 * state.ra2 * e);
+}
 
 impl fmt::Debug for PvCell {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{{{},{}}}", self.ns, self.np)
     }
-}
\ No newline at end of file
+}
+
+impl Element for PvCell {
+    type State = PvCellState;
+
+    fn compute_state(&self, irrad_ef: f64, cell_temp: f64) -> PvCellState {
+        return PvCell::compute_state(self, irrad_ef, cell_temp);
+    }
+
+    fn current_at(&self, state: &PvCellState, v: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if strict {
+            return self.try_solve_i_with_stats(state, v, stats);
+        }
+        return Ok(self.solve_i_lenient(state, v, stats));
+    }
+
+    fn voltage_at(&self, state: &PvCellState, i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if strict {
+            return self.try_v_from_i_with_stats(state, i, stats);
+        }
+        return Ok(self.v_from_i_lenient(state, i, stats));
+    }
+
+    fn conductance_at(&self, state: &PvCellState, v: f64, i: f64, _strict: bool, _stats: &mut SolverStats) -> Result<f64, SolverError> {
+        return Ok(self.conductance(state, v, i));
+    }
+
+    fn nominal_voc(&self) -> f64 {
+        return self.v_oc_guess() * (self.ns as f64);
+    }
+
+    fn nominal_il(&self) -> f64 {
+        return self.i_l_ref * (self.np as f64);
+    }
+
+    fn mpp_grid(&self) -> usize {
+        return MPP_GRID;
+    }
+
+    fn reduced(&self) -> PvCell {
+        return self.clone();
+    }
+
+    fn series_ratio(&self, other: &PvCell) -> Option<f64> {
+        if !self.is_series_equivalent(other) {
+            return None;
+        }
+        return Some(other.ns as f64 / self.ns as f64);
+    }
+
+    fn parallel_ratio(&self, other: &PvCell) -> Option<f64> {
+        if !self.is_parallel_equivalent(other) {
+            return None;
+        }
+        return Some(other.np as f64 / self.np as f64);
+    }
+
+    fn merge_series(&mut self, other: &PvCell) -> bool {
+        if !self.is_series_equivalent(other) {
+            return false;
+        }
+        self.ns += other.ns;
+        return true;
+    }
+
+    fn merge_parallel(&mut self, other: &PvCell) -> bool {
+        if !self.is_parallel_equivalent(other) {
+            return false;
+        }
+        self.np += other.np;
+        return true;
+    }
+}
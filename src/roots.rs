@@ -0,0 +1,137 @@
+/// Result of a scalar root search.
+pub(crate) struct Root {
+    pub x: f64,
+    pub fx: f64,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Brent's method on the bracket `[a, b]` (`fa` and `fb` of opposite signs).
+///
+/// Stops when `|f(x)| <= tol_f` or when the bracket shrinks to machine precision.
+/// Errors returned by `f` are propagated unchanged.
+pub(crate) fn brent<F, E>(mut f: F, mut a: f64, mut b: f64, mut fa: f64, mut fb: f64, tol_f: f64, max_iter: usize) -> Result<Root, E>
+where F: FnMut(f64) -> Result<f64, E> {
+    let mut c: f64 = b;
+    let mut fc: f64 = fb;
+    let mut d: f64 = b - a;
+    let mut e: f64 = d;
+
+    for iterations in 1..=max_iter {
+        if (fb > 0.0 && fc > 0.0) || (fb < 0.0 && fc < 0.0) {
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+        let tol1: f64 = 2.0 * f64::EPSILON * b.abs() + f64::MIN_POSITIVE;
+        let xm: f64 = 0.5 * (c - b);
+        if fb.abs() <= tol_f || xm.abs() <= tol1 {
+            return Ok(Root { x: b, fx: fb, iterations, converged: true });
+        }
+
+        if e.abs() >= tol1 && fa.abs() > fb.abs() {
+            // interpolação inversa (secante ou quadrática)
+            let s: f64 = fb / fa;
+            let (mut p, mut q): (f64, f64);
+            if a == c {
+                p = 2.0 * xm * s;
+                q = 1.0 - s;
+            } else {
+                let qa: f64 = fa / fc;
+                let r: f64 = fb / fc;
+                p = s * (2.0 * xm * qa * (qa - r) - (b - a) * (r - 1.0));
+                q = (qa - 1.0) * (r - 1.0) * (s - 1.0);
+            }
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+            let min1: f64 = 3.0 * xm * q - (tol1 * q).abs();
+            let min2: f64 = (e * q).abs();
+            if 2.0 * p < min1.min(min2) {
+                e = d;
+                d = p / q;
+            } else {
+                d = xm;
+                e = d;
+            }
+        } else {
+            // bisseção
+            d = xm;
+            e = d;
+        }
+        a = b;
+        fa = fb;
+        b += if d.abs() > tol1 { d } else { tol1.copysign(xm) };
+        fb = f(b)?;
+    }
+    return Ok(Root { x: b, fx: fb, iterations: max_iter, converged: fb.abs() <= tol_f });
+}
+
+/// Widens `[lo, hi]` until `f(lo) >= 0 >= f(hi)` (for decreasing `f`), doubling the width at each step.
+///
+/// Returns `(lo, hi, f(lo), f(hi))`, or `None` if no sign change was found in `max_steps`.
+pub(crate) fn bracket_decreasing<F, E>(mut f: F, mut lo: f64, mut hi: f64, max_steps: usize) -> Result<Option<(f64, f64, f64, f64)>, E>
+where F: FnMut(f64) -> Result<f64, E> {
+    let mut f_lo: f64 = f(lo)?;
+    let mut f_hi: f64 = f(hi)?;
+    for _ in 0..max_steps {
+        if f_lo >= 0.0 && f_hi <= 0.0 {
+            return Ok(Some((lo, hi, f_lo, f_hi)));
+        }
+        let width: f64 = (hi - lo).abs().max(1.0);
+        if f_lo < 0.0 {
+            lo -= width;
+            f_lo = f(lo)?;
+        }
+        if f_hi > 0.0 {
+            hi += width;
+            f_hi = f(hi)?;
+        }
+    }
+    if f_lo >= 0.0 && f_hi <= 0.0 {
+        return Ok(Some((lo, hi, f_lo, f_hi)));
+    }
+    return Ok(None);
+}
+
+/// Newton's method kept inside the bracket `[lo, hi]` of a decreasing `f` (`f(lo) >= 0 >= f(hi)`).
+///
+/// `f` returns the value and the derivative. Steps that leave the bracket are replaced by bisection;
+/// stops when `|f(x)| <= tol_f` or when the step falls below `tol_x`.
+pub(crate) fn newton_bracketed<F, E>(mut f: F, mut lo: f64, mut hi: f64, x0: f64, tol_x: f64, tol_f: f64, max_iter: usize) -> Result<Root, E>
+where F: FnMut(f64) -> Result<(f64, f64), E> {
+    let mut x: f64 = x0.clamp(lo, hi);
+    let mut fx: f64 = f64::NAN;
+    for iterations in 1..=max_iter {
+        let (fv, dfv) = f(x)?;
+        fx = fv;
+        if fx.abs() <= tol_f {
+            return Ok(Root { x, fx, iterations, converged: true });
+        }
+        if fx > 0.0 {
+            lo = x;
+        } else {
+            hi = x;
+        }
+        let mut x_new: f64 = x - fx / dfv;
+        if !(x_new > lo && x_new < hi) {
+            x_new = 0.5 * (lo + hi);  // bisseção
+        }
+        let step: f64 = x_new - x;
+        x = x_new;
+        if step.abs() < tol_x || (hi - lo) <= tol_x.max(4.0 * f64::EPSILON * x.abs()) {
+            return Ok(Root { x, fx, iterations, converged: true });
+        }
+    }
+    return Ok(Root { x, fx, iterations: max_iter, converged: false });
+}
@@ -1,18 +1,37 @@
-use std::cmp::PartialEq;
-use crate::pvcell::{PvCellState, PvCell};
+use crate::pvcell::PvCell;
+use crate::element::{Element, common_ratio};
+use crate::error::{OperatingPoint, SolverError};
+use crate::stats::SolverStats;
+use crate::roots::{brent, bracket_decreasing, newton_bracketed};
+use crate::small_signal::SmallSignal;
+use crate::mpp::{Mpp, global_mpp};
+use crate::curve::{CurveOptions, IvCurve, build_curve};
+use crate::thermal::{Ambient, ThermalModel};
+use std::convert::Infallible;
 use std::ops::Index;
 use std::iter::IntoIterator;
 use tracing::{warn, error};
 use std::fmt;
 
+const MPP_GRID_PER_ELEMENT: usize = 10;
+const MPP_GRID_MIN: usize = 50;
 
-pub static mut SOLVER_CALLS: usize = 0;
+
+/// Iteration used by `Series::i_from_v`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SeriesMethod {
+    #[default]
+    Gain,   // fixed-point on the current with gain `g`, halved on sign change and clamped by `min_g`
+    Brent,  // bracketed Brent search on the (decreasing) string voltage: always converges on monotonic curves
+    Newton, // bracketed Newton with the analytic dV/dI of the elements (bisection when a step leaves the bracket)
+}
 
 #[derive(Debug, Clone)]
 pub struct SeriesSolver {
     pub max_iter: usize,
     pub tol_v: f64,
-    pub min_g: f64,
+    pub min_g: f64,     // only used by `SeriesMethod::Gain`
+    pub method: SeriesMethod,
 }
 
 impl Default for SeriesSolver {
@@ -21,27 +40,28 @@ impl Default for SeriesSolver {
             max_iter: 1000,
             tol_v: 0.1,
             min_g: 0.00001,
+            method: SeriesMethod::Gain,
         }
     }
 }
 
+/// Elements in series (cells or modules by default, any [`Element`] when nested).
 #[derive(Clone)]
-pub struct Series {
-    pub elements: Vec<PvCell>,
+pub struct Series<E: Element = PvCell> {
+    pub elements: Vec<E>,
     pub solver: SeriesSolver,
 }
 
 
-#[allow(dead_code)]
-impl Series {
-    pub fn empty() -> Series {
+impl<E: Element> Series<E> {
+    pub fn empty() -> Self {
         return Series {
             elements: Vec::new(),
             solver: SeriesSolver::default()
         };
     }
 
-    pub fn new(panels: Vec<PvCell>) -> Series {
+    pub fn new(panels: Vec<E>) -> Self {
         return Series {
             elements: panels,
             solver: SeriesSolver::default()
@@ -50,61 +70,178 @@ impl Series {
     
 
     /// builder: let s: Series = Series::new(...).with_solver(...);
-    pub fn with_solver(mut self, settings: SeriesSolver) -> Series { self.solver = settings; return self; }
+    pub fn with_solver(mut self, settings: SeriesSolver) -> Self { self.solver = settings; return self; }
 
     pub fn len(&self) -> usize {
         return self.elements.len();
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &PvCell> {
+    pub fn is_empty(&self) -> bool {
+        return self.elements.is_empty();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
         self.elements.iter()
     }
 
-    pub fn push(&mut self, element: PvCell){
+    pub fn push(&mut self, element: E){
         self.elements.push(element);
     }
 
-    pub fn states_uniform_conditions(&self, irrad_ef: f64, cell_temp: f64) -> Vec<PvCellState> {
-        let mut states: Vec<PvCellState> = Vec::with_capacity(self.len());
+    pub fn states_uniform_conditions(&self, irrad_ef: f64, cell_temp: f64) -> Vec<E::State> {
+        let mut states: Vec<E::State> = Vec::with_capacity(self.len());
         for pnl in self.elements.iter(){
             states.push(pnl.compute_state(irrad_ef, cell_temp));
         }
         return states;
     }
 
-    pub fn vs_from_i(&self, states: &Vec<PvCellState>, i: f64) -> Vec<f64> {
+    /// One irradiance and temperature per element of this string (e.g. a shading map for one timestep).
+    pub fn states_conditions(&self, irrad_ef: &[f64], cell_temp: &[f64]) -> Vec<E::State> {
+        assert_eq!(irrad_ef.len(), self.len(), "Series::states_conditions: uma irradiância por elemento");
+        assert_eq!(cell_temp.len(), self.len(), "Series::states_conditions: uma temperatura por elemento");
+        let mut states: Vec<E::State> = Vec::with_capacity(self.len());
+        for (k, pnl) in self.elements.iter().enumerate(){
+            states.push(pnl.compute_state(irrad_ef[k], cell_temp[k]));
+        }
+        return states;
+    }
+
+    /// Weather data per element: the cell temperature follows the POA global irradiance
+    /// `poa_global`, the state the effective irradiance `irrad_ef` (after shading and optical losses).
+    pub fn states_ambient(&self, poa_global: &[f64], irrad_ef: &[f64], ambient: &Ambient, thermal: &ThermalModel) -> Vec<E::State> {
+        assert_eq!(poa_global.len(), irrad_ef.len(), "Series::states_ambient: uma irradiância POA por irradiância efetiva");
+        return self.states_conditions(irrad_ef, &thermal.cell_temperatures(poa_global, ambient));
+    }
+
+    /// Uniform conditions with a shading fraction per element, applied on top of `PvCell::shading`.
+    pub fn states_shading(&self, irrad_ef: f64, cell_temp: f64, shading: &[f64]) -> Vec<E::State> {
+        assert_eq!(shading.len(), self.len(), "Series::states_shading: um sombreamento por elemento");
+        let mut states: Vec<E::State> = Vec::with_capacity(self.len());
+        for (k, pnl) in self.elements.iter().enumerate(){
+            states.push(pnl.compute_state(irrad_ef * (1.0 - shading[k]), cell_temp));
+        }
+        return states;
+    }
+
+    pub fn vs_from_i(&self, states: &[E::State], i: f64) -> Vec<f64> {
+        self.vs_from_i_lenient(states, i, &mut SolverStats::new())
+    }
+
+    pub fn try_vs_from_i(&self, states: &[E::State], i: f64) -> Result<Vec<f64>, SolverError> {
+        self.try_vs_from_i_with_stats(states, i, &mut SolverStats::new())
+    }
+
+    pub fn try_vs_from_i_with_stats(&self, states: &[E::State], i: f64, stats: &mut SolverStats) -> Result<Vec<f64>, SolverError> {
+        self.vs_from_i_impl(states, i, true, stats)
+    }
+
+    pub(crate) fn vs_from_i_lenient(&self, states: &[E::State], i: f64, stats: &mut SolverStats) -> Vec<f64> {
+        self.vs_from_i_impl(states, i, false, stats).unwrap_or_else(|_| vec![f64::NAN; self.len()])
+    }
+
+    fn vs_from_i_impl(&self, states: &[E::State], i: f64, strict: bool, stats: &mut SolverStats) -> Result<Vec<f64>, SolverError> {
         let mut voltages: Vec<f64> = Vec::with_capacity(self.len());
         for (k, pnl) in self.iter().enumerate(){
-            voltages.push(pnl.v_from_i(&states[k], i))
+            voltages.push(pnl.voltage_at(&states[k], i, strict, stats)?)
         }
-        voltages
+        Ok(voltages)
+    }
+
+    pub fn v_from_i(&self, states: &[E::State], i: f64) -> f64 {
+        self.v_from_i_lenient(states, i, &mut SolverStats::new())
+    }
+
+    /// Lenient, accumulating the solver statistics in `stats`.
+    pub fn v_from_i_with_stats(&self, states: &[E::State], i: f64, stats: &mut SolverStats) -> f64 {
+        self.v_from_i_lenient(states, i, stats)
+    }
+
+    pub fn try_v_from_i(&self, states: &[E::State], i: f64) -> Result<f64, SolverError> {
+        self.try_v_from_i_with_stats(states, i, &mut SolverStats::new())
     }
 
-    pub fn v_from_i(&self, states: &Vec<PvCellState>, i: f64) -> f64 {
-        let voltages = self.vs_from_i(states, i);
+    pub fn try_v_from_i_with_stats(&self, states: &[E::State], i: f64, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let voltages = self.try_vs_from_i_with_stats(states, i, stats)?;
+        Ok(voltages.iter().sum())
+    }
+
+    pub(crate) fn v_from_i_lenient(&self, states: &[E::State], i: f64, stats: &mut SolverStats) -> f64 {
+        let voltages = self.vs_from_i_lenient(states, i, stats);
         voltages.iter().sum()
     }
 
-    pub fn i_from_v(&self, states: &Vec<PvCellState>, v_str: f64) -> f64 {
-        unsafe{ SOLVER_CALLS += 1; }
+    pub fn i_from_v(&self, states: &[E::State], v_str: f64) -> f64 {
+        self.i_from_v_lenient(states, v_str, &mut SolverStats::new())
+    }
+
+    /// Lenient, accumulating the solver statistics in `stats`.
+    pub fn i_from_v_with_stats(&self, states: &[E::State], v_str: f64, stats: &mut SolverStats) -> f64 {
+        self.i_from_v_lenient(states, v_str, stats)
+    }
+
+    /// Fails if the string iteration or any of the element solves does not converge.
+    pub fn try_i_from_v(&self, states: &[E::State], v_str: f64) -> Result<f64, SolverError> {
+        self.try_i_from_v_with_stats(states, v_str, &mut SolverStats::new())
+    }
+
+    pub fn try_i_from_v_with_stats(&self, states: &[E::State], v_str: f64, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        self.i_from_v_impl(states, v_str, true, stats)
+    }
+
+    /// Logs a non-converged solve and returns its last iterate.
+    pub(crate) fn i_from_v_lenient(&self, states: &[E::State], v_str: f64, stats: &mut SolverStats) -> f64 {
+        match self.i_from_v_impl(states, v_str, false, stats) {
+            Ok(i) => i,
+            Err(e) => {
+                let i0 = e.last_value();
+                if i0.is_normal() {
+                    warn!("({:p}) Series::i_from_v(v_str={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
+                        &self, v_str, self.solver.tol_v, self.solver.max_iter, i0);
+                } else {
+                    error!("({:p}) Series::i_from_v(v_str={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
+                        &self, v_str, self.solver.tol_v, self.solver.max_iter, i0);
+                }
+                i0
+            }
+        }
+    }
 
+    /// `strict`: propagate failures of the element solves instead of using their last iterates
+    fn i_from_v_impl(&self, states: &[E::State], v_str: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
         let mut sum_voc: f64 = 0.0;
         let mut il: f64 = f64::INFINITY;
         let mut i0: f64 = f64::INFINITY;
+        let mut isc_max: f64 = f64::NEG_INFINITY;
         for (k, pnl) in self.elements.iter().enumerate() {
-            sum_voc += pnl.v_oc_ref * (pnl.ns as f64);
-            il = il.min(pnl.i_l_ref * (pnl.np as f64));
-            i0 = i0.min(pnl.solve_i(&states[k], 0.0));
+            sum_voc += pnl.nominal_voc();
+            il = il.min(pnl.nominal_il());
+            let isc = pnl.current_at(&states[k], 0.0, strict, stats)?;
+            i0 = i0.min(isc);
+            isc_max = isc_max.max(isc);
         }
+        match self.solver.method {
+            SeriesMethod::Gain => {},
+            SeriesMethod::Brent => return self.brent_i_from_v(states, v_str, i0, isc_max, strict, stats),
+            SeriesMethod::Newton => return self.newton_i_from_v(states, v_str, i0, isc_max, strict, stats),
+        }
+
         let mut g: f64 = il / sum_voc;
         // let mut g: f64 = 10.0 / sum_voc;
 
         let mut dv1: f64 = 0.0;
+        let mut iterations: usize = 0;
         for _ in 0..self.solver.max_iter {
-            let v0 = self.v_from_i(states, i0);
+            iterations += 1;
+            let v0 = if strict {
+                self.try_v_from_i_with_stats(states, i0, stats)?
+            } else {
+                self.v_from_i_lenient(states, i0, stats)
+            };
             let dv = v0 - v_str;
             if dv.abs() < self.solver.tol_v {
-                return i0;
+                stats.series.record(iterations, dv.abs(), true);
+                return Ok(i0);
             }
             if dv * dv1 < 0.0 {  // mudança de sinal
                 g /= 2.0;
@@ -113,17 +250,131 @@ impl Series {
             i0 += dv * g;
             dv1 = dv;
         }
+        stats.series.record(iterations, dv1.abs(), false);
+        return Err(SolverError::not_converged(iterations, dv1.abs(), self.solver.tol_v, OperatingPoint::Voltage(v_str), i0));
+    }
 
-        if i0.is_normal() {
-            warn!("({:p}) Series::i_from_v(v_str={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
-                &self, v_str, self.solver.tol_v, self.solver.max_iter, i0);
-        } else {
-            error!("({:p}) Series::i_from_v(v_str={:e}) nao convergiu (tol={:e}, max_iter={}) -> (i={})",
-                &self, v_str, self.solver.tol_v, self.solver.max_iter, i0);
+    /// The string voltage decreases with the current: brackets the solution starting from the
+    /// short-circuit currents of the elements and refines it with Brent's method.
+    fn brent_i_from_v(&self, states: &[E::State], v_str: f64, isc_min: f64, isc_max: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let mut residual = |i: f64| -> Result<f64, SolverError> {
+            let v = if strict {
+                self.try_v_from_i_with_stats(states, i, stats)?
+            } else {
+                self.v_from_i_lenient(states, i, stats)
+            };
+            Ok(v - v_str)
+        };
+        let (lo, hi): (f64, f64) = if isc_min.is_finite() && isc_max.is_finite() { (isc_min, isc_max) } else { (0.0, 1.0) };
+        let root = match bracket_decreasing(&mut residual, lo, hi, 60)? {
+            Some((lo, hi, f_lo, f_hi)) => brent(&mut residual, lo, hi, f_lo, f_hi, self.solver.tol_v, self.solver.max_iter)?,
+            None => {
+                stats.series.record(0, f64::NAN, false);
+                return Err(SolverError::not_converged(0, f64::NAN, self.solver.tol_v, OperatingPoint::Voltage(v_str), f64::NAN));
+            }
+        };
+        stats.series.record(root.iterations, root.fx.abs(), root.converged);
+        if !root.converged {
+            return Err(SolverError::not_converged(root.iterations, root.fx.abs(), self.solver.tol_v, OperatingPoint::Voltage(v_str), root.x));
         }
-        return i0;
+        return Ok(root.x);
+    }
+
+    fn newton_i_from_v(&self, states: &[E::State], v_str: f64, isc_min: f64, isc_max: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let mut residual = |i: f64| -> Result<(f64, f64), SolverError> {
+            let (v, r) = self.v_and_dv_di(states, i, strict, stats)?;
+            Ok((v - v_str, r))
+        };
+        let (lo, hi): (f64, f64) = if isc_min.is_finite() && isc_max.is_finite() { (isc_min, isc_max) } else { (0.0, 1.0) };
+        let bracket = bracket_decreasing(|i| Ok(residual(i)?.0), lo, hi, 60)?;
+        let root = match bracket {
+            Some((lo, hi, _, _)) => newton_bracketed(&mut residual, lo, hi, isc_min, 0.0, self.solver.tol_v, self.solver.max_iter)?,
+            None => {
+                stats.series.record(0, f64::NAN, false);
+                return Err(SolverError::not_converged(0, f64::NAN, self.solver.tol_v, OperatingPoint::Voltage(v_str), f64::NAN));
+            }
+        };
+        stats.series.record(root.iterations, root.fx.abs(), root.converged);
+        if !root.converged {
+            return Err(SolverError::not_converged(root.iterations, root.fx.abs(), self.solver.tol_v, OperatingPoint::Voltage(v_str), root.x));
+        }
+        return Ok(root.x);
+    }
+
+    /// String voltage and dV/dI [Ohm] at the current `i`.
+    pub(crate) fn v_and_dv_di(&self, states: &[E::State], i: f64, strict: bool, stats: &mut SolverStats) -> Result<(f64, f64), SolverError> {
+        let voltages = self.vs_from_i_impl(states, i, strict, stats)?;
+        let mut r: f64 = 0.0;
+        for (k, pnl) in self.iter().enumerate() {
+            r += 1.0 / pnl.conductance_at(&states[k], voltages[k], i, strict, stats)?;
+        }
+        return Ok((voltages.iter().sum(), r));
+    }
+
+    pub fn small_signal(&self, states: &[E::State], v_str: f64) -> SmallSignal {
+        let mut stats = SolverStats::new();
+        let i: f64 = self.i_from_v_lenient(states, v_str, &mut stats);
+        let (_, r) = self.v_and_dv_di(states, i, false, &mut stats).unwrap_or((f64::NAN, f64::NAN));
+        return SmallSignal { v: v_str, i, g: 1.0 / r };
     }
 
+    pub fn try_small_signal(&self, states: &[E::State], v_str: f64) -> Result<SmallSignal, SolverError> {
+        self.try_small_signal_with_stats(states, v_str, &mut SolverStats::new())
+    }
+
+    pub fn try_small_signal_with_stats(&self, states: &[E::State], v_str: f64, stats: &mut SolverStats) -> Result<SmallSignal, SolverError> {
+        let i: f64 = self.try_i_from_v_with_stats(states, v_str, stats)?;
+        let (_, r) = self.v_and_dv_di(states, i, true, stats)?;
+        return Ok(SmallSignal { v: v_str, i, g: 1.0 / r });
+    }
+
+    /// Global MPP, also on multi-peak curves caused by bypass diodes.
+    pub fn mpp(&self, states: &[E::State]) -> Mpp {
+        let mut stats = SolverStats::new();
+        let v_oc: f64 = self.v_from_i_lenient(states, 0.0, &mut stats);
+        let Ok(mpp) = global_mpp(|v| Ok::<f64, Infallible>(self.i_from_v_lenient(states, v, &mut stats)), v_oc, self.mpp_grid());
+        return mpp;
+    }
+
+    pub fn try_mpp(&self, states: &[E::State]) -> Result<Mpp, SolverError> {
+        self.try_mpp_with_stats(states, &mut SolverStats::new())
+    }
+
+    pub fn try_mpp_with_stats(&self, states: &[E::State], stats: &mut SolverStats) -> Result<Mpp, SolverError> {
+        let v_oc: f64 = self.try_v_from_i_with_stats(states, 0.0, stats)?;
+        return global_mpp(|v| self.try_i_from_v_with_stats(states, v, stats), v_oc, self.mpp_grid());
+    }
+
+    /// MPP under uniform conditions, together with the states it was computed from.
+    pub fn mpp_at(&self, irrad_ef: f64, cell_temp: f64) -> (Mpp, Vec<E::State>) {
+        let states = self.states_uniform_conditions(irrad_ef, cell_temp);
+        return (self.mpp(&states), states);
+    }
+
+    /// I-V curve from short circuit to open circuit, refined at the knees of bypassed elements.
+    pub fn iv_curve(&self, states: &[E::State], opts: &CurveOptions) -> IvCurve {
+        let mut stats = SolverStats::new();
+        let isc: f64 = self.i_from_v_lenient(states, 0.0, &mut stats);
+        let voc: f64 = self.v_from_i_lenient(states, 0.0, &mut stats);
+        let mpp: Mpp = self.mpp(states);
+        let Ok(curve) = build_curve(|v| Ok::<f64, Infallible>(self.i_from_v_lenient(states, v, &mut stats)), isc, voc, mpp, opts);
+        return curve;
+    }
+
+    pub fn try_iv_curve(&self, states: &[E::State], opts: &CurveOptions) -> Result<IvCurve, SolverError> {
+        self.try_iv_curve_with_stats(states, opts, &mut SolverStats::new())
+    }
+
+    pub fn try_iv_curve_with_stats(&self, states: &[E::State], opts: &CurveOptions, stats: &mut SolverStats) -> Result<IvCurve, SolverError> {
+        let isc: f64 = self.try_i_from_v_with_stats(states, 0.0, stats)?;
+        let voc: f64 = self.try_v_from_i_with_stats(states, 0.0, stats)?;
+        let mpp: Mpp = self.try_mpp_with_stats(states, stats)?;
+        return build_curve(|v| self.try_i_from_v_with_stats(states, v, stats), isc, voc, mpp, opts);
+    }
+
+}
+
+impl Series {
     pub fn find_series_equivalent(&self, other: &PvCell) -> Option<usize> {
         for (k, pnl) in self.elements.iter().enumerate(){
             if pnl.is_series_equivalent(other) {
@@ -134,20 +385,20 @@ impl Series {
     }
 
     pub fn reduce(&self) -> (Series, Vec<u32>, Vec<Vec<u32>>) {
-        let mut reduced: Series = Series::empty();
+        let mut reduced: Series = Series::empty().with_solver(self.solver.clone());
         let mut origin_to_reduced: Vec<u32> = vec![0; self.len()];
         let mut reduced_to_origin: Vec<Vec<u32>> = vec![];
 
-        for i in 0..self.elements.len() {
-            match reduced.find_series_equivalent(&self.elements[i]) {
+        for (i, pnl) in self.elements.iter().enumerate() {
+            match reduced.find_series_equivalent(pnl) {
                 Some(j) => {
-                    reduced.elements[j].ns += self.elements[i].ns;
+                    reduced.elements[j].ns += pnl.ns;
                     reduced_to_origin[j].push(i as u32);
                     origin_to_reduced[i] = j as u32;
                 }
                 None => {
                     origin_to_reduced[i] = reduced.elements.len() as u32;
-                    reduced.elements.push(self.elements[i].clone());
+                    reduced.elements.push(pnl.clone());
                     reduced_to_origin.push(vec![i as u32]);
                 }
             }
@@ -155,35 +406,127 @@ impl Series {
         return (reduced, origin_to_reduced, reduced_to_origin);
     }
 
+    /// Element-wise parallel equivalence. The `np` of the elements must also be proportional,
+    /// otherwise the merged string would not split its current equally among the originals.
     pub fn is_parallel_equivalent(&self, other: &Series) -> bool {
         if self.len() != other.len(){ 
             return false; 
         }
+        let (Some(s0), Some(o0)) = (self.elements.first(), other.elements.first()) else {
+            return true;
+        };
         for (s, o) in self.elements.iter().zip(other.elements.iter()) {
             if !s.is_parallel_equivalent(o) {
                 return false; 
             }
+            if (s.np as u64) * (o0.np as u64) != (o.np as u64) * (s0.np as u64) {
+                return false;
+            }
+        }
+        return true;
+    }
+}
+
+impl<E: Element> Element for Series<E> {
+    type State = Vec<E::State>;
+
+    fn compute_state(&self, irrad_ef: f64, cell_temp: f64) -> Vec<E::State> {
+        return self.states_uniform_conditions(irrad_ef, cell_temp);
+    }
+
+    fn current_at(&self, states: &Vec<E::State>, v: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        if strict {
+            return self.try_i_from_v_with_stats(states, v, stats);
+        }
+        return Ok(self.i_from_v_lenient(states, v, stats));
+    }
+
+    fn voltage_at(&self, states: &Vec<E::State>, i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let voltages = self.vs_from_i_impl(states, i, strict, stats)?;
+        return Ok(voltages.iter().sum());
+    }
+
+    fn conductance_at(&self, states: &Vec<E::State>, _v: f64, i: f64, strict: bool, stats: &mut SolverStats) -> Result<f64, SolverError> {
+        let (_, r) = self.v_and_dv_di(states, i, strict, stats)?;
+        return Ok(1.0 / r);
+    }
+
+    fn nominal_voc(&self) -> f64 {
+        return self.elements.iter().map(|e| e.nominal_voc()).sum();
+    }
+
+    fn nominal_il(&self) -> f64 {
+        return self.elements.iter().map(|e| e.nominal_il()).fold(f64::INFINITY, f64::min);
+    }
+
+    fn mpp_grid(&self) -> usize {
+        return (MPP_GRID_PER_ELEMENT * self.len()).max(MPP_GRID_MIN);
+    }
+
+    /// The order of elements in series does not matter: any two equivalent elements are merged.
+    fn reduced(&self) -> Self {
+        let mut reduced: Series<E> = Series::empty().with_solver(self.solver.clone());
+        for e in self.elements.iter() {
+            let e = e.reduced();
+            if !reduced.elements.iter_mut().any(|r| r.merge_series(&e)) {
+                reduced.elements.push(e);
+            }
+        }
+        return reduced;
+    }
+
+    /// Element-wise, with the same ratio for every element.
+    fn series_ratio(&self, other: &Self) -> Option<f64> {
+        return common_ratio(&self.elements, &other.elements, |s, o| s.series_ratio(o));
+    }
+
+    /// Element-wise, with the same ratio for every element (see `Series::is_parallel_equivalent`).
+    fn parallel_ratio(&self, other: &Self) -> Option<f64> {
+        return common_ratio(&self.elements, &other.elements, |s, o| s.parallel_ratio(o));
+    }
+
+    fn merge_series(&mut self, other: &Self) -> bool {
+        for o in other.elements.iter() {
+            if !self.elements.iter_mut().any(|s| s.merge_series(o)) {
+                self.elements.push(o.clone());
+            }
         }
         return true;
     }
+
+    fn merge_parallel(&mut self, other: &Self) -> bool {
+        if self.parallel_ratio(other).is_none() {
+            return false;
+        }
+        for (s, o) in self.elements.iter_mut().zip(other.elements.iter()) {
+            s.merge_parallel(o);
+        }
+        return true;
+    }
+}
+
+impl<E: Element> Default for Series<E> {
+    fn default() -> Self {
+        return Series::empty();
+    }
 }
 
-impl fmt::Debug for Series {
+impl<E: Element + fmt::Debug> fmt::Debug for Series<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Series{:?}", &self.elements)
     }
 }
 
-impl Index<usize> for Series {
-    type Output = PvCell;
+impl<E: Element> Index<usize> for Series<E> {
+    type Output = E;
     fn index(&self, index: usize) -> &Self::Output {
         &self.elements[index]
     }
 }
 
-impl IntoIterator for Series {
-    type Item = PvCell;
-    type IntoIter = std::vec::IntoIter<PvCell>;
+impl<E: Element> IntoIterator for Series<E> {
+    type Item = E;
+    type IntoIter = std::vec::IntoIter<E>;
     fn into_iter(self) -> Self::IntoIter {
         return self.elements.into_iter();
     }
@@ -0,0 +1,24 @@
+/// Linearization of an element at an operating point: `i' ≈ i + g * (v' - v)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmallSignal {
+    pub v: f64, // [V]
+    pub i: f64, // [A]
+    pub g: f64, // [S] dI/dV (negative for a generator)
+}
+
+impl SmallSignal {
+    /// dV/dI [Ohm]
+    pub fn r(&self) -> f64 {
+        return 1.0 / self.g;
+    }
+
+    /// Norton equivalent: current source `i_n` [A] in parallel with the conductance `g_n` [S].
+    pub fn norton(&self) -> (f64, f64) {
+        return (self.i - self.g * self.v, -self.g);
+    }
+
+    /// Thevenin equivalent: voltage source `v_th` [V] in series with the resistance `r_th` [Ohm].
+    pub fn thevenin(&self) -> (f64, f64) {
+        return (self.v - self.i / self.g, -1.0 / self.g);
+    }
+}
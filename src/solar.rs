@@ -0,0 +1,381 @@
+use crate::irradiance::SOLAR_CONSTANT;
+
+/// Default TT - UT [s] (about 69 s in the 2020s).
+pub const DELTA_T_DEFAULT: f64 = 69.0;
+
+const J2000: f64 = 2451545.0;               // [d] Julian day of 2000-01-01 12:00 TT
+const EARTH_RADIUS: f64 = 6378140.0;        // [m]
+const SUN_RADIUS: f64 = 0.26667;            // [deg] apparent
+const ATMOS_REFRACT: f64 = 0.5667;          // [deg] refraction at sunrise/sunset
+
+// Termos periódicos da Terra (Meeus / SPA, tabela A4.2): [A, B, C] -> A * cos(B + C * JME)
+const L0: [[f64; 3]; 64] = [
+    [175347046.0, 0.0, 0.0], [3341656.0, 4.6692568, 6283.07585], [34894.0, 4.6261, 12566.1517],
+    [3497.0, 2.7441, 5753.3849], [3418.0, 2.8289, 3.5231], [3136.0, 3.6277, 77713.7715],
+    [2676.0, 4.4181, 7860.4194], [2343.0, 6.1352, 3930.2097], [1324.0, 0.7425, 11506.7698],
+    [1273.0, 2.0371, 529.691], [1199.0, 1.1096, 1577.3435], [990.0, 5.233, 5884.927],
+    [902.0, 2.045, 26.298], [857.0, 3.508, 398.149], [780.0, 1.179, 5223.694],
+    [753.0, 2.533, 5507.553], [505.0, 4.583, 18849.228], [492.0, 4.205, 775.523],
+    [357.0, 2.92, 0.067], [317.0, 5.849, 11790.629], [284.0, 1.899, 796.298],
+    [271.0, 0.315, 10977.079], [243.0, 0.345, 5486.778], [206.0, 4.806, 2544.314],
+    [205.0, 1.869, 5573.143], [202.0, 2.458, 6069.777], [156.0, 0.833, 213.299],
+    [132.0, 3.411, 2942.463], [126.0, 1.083, 20.775], [115.0, 0.645, 0.98],
+    [103.0, 0.636, 4694.003], [102.0, 0.976, 15720.839], [102.0, 4.267, 7.114],
+    [99.0, 6.21, 2146.17], [98.0, 0.68, 155.42], [86.0, 5.98, 161000.69],
+    [85.0, 1.3, 6275.96], [85.0, 3.67, 71430.7], [80.0, 1.81, 17260.15],
+    [79.0, 3.04, 12036.46], [75.0, 1.76, 5088.63], [74.0, 3.5, 3154.69],
+    [74.0, 4.68, 801.82], [70.0, 0.83, 9437.76], [62.0, 3.98, 8827.39],
+    [61.0, 1.82, 7084.9], [57.0, 2.78, 6286.6], [56.0, 4.39, 14143.5],
+    [56.0, 3.47, 6279.55], [52.0, 0.19, 12139.55], [52.0, 1.33, 1748.02],
+    [51.0, 0.28, 5856.48], [49.0, 0.49, 1194.45], [41.0, 5.37, 8429.24],
+    [41.0, 2.4, 19651.05], [39.0, 6.17, 10447.39], [37.0, 6.04, 10213.29],
+    [37.0, 2.57, 1059.38], [36.0, 1.71, 2352.87], [36.0, 1.78, 6812.77],
+    [33.0, 0.59, 17789.85], [30.0, 0.44, 83996.85], [30.0, 2.74, 1349.87],
+    [25.0, 3.16, 4690.48],
+];
+const L1: [[f64; 3]; 34] = [
+    [628331966747.0, 0.0, 0.0], [206059.0, 2.678235, 6283.07585], [4303.0, 2.6351, 12566.1517],
+    [425.0, 1.59, 3.523], [119.0, 5.796, 26.298], [109.0, 2.966, 1577.344],
+    [93.0, 2.59, 18849.23], [72.0, 1.14, 529.69], [68.0, 1.87, 398.15],
+    [67.0, 4.41, 5507.55], [59.0, 2.89, 5223.69], [56.0, 2.17, 155.42],
+    [45.0, 0.4, 796.3], [36.0, 0.47, 775.52], [29.0, 2.65, 7.11],
+    [21.0, 5.34, 0.98], [19.0, 1.85, 5486.78], [19.0, 4.97, 213.3],
+    [17.0, 2.99, 6275.96], [16.0, 0.03, 2544.31], [16.0, 1.43, 2146.17],
+    [15.0, 1.21, 10977.08], [12.0, 2.83, 1748.02], [12.0, 3.26, 5088.63],
+    [12.0, 5.27, 1194.45], [12.0, 2.08, 4694.0], [11.0, 0.77, 553.57],
+    [10.0, 1.3, 6286.6], [10.0, 4.24, 1349.87], [9.0, 2.7, 242.73],
+    [9.0, 5.64, 951.72], [8.0, 5.3, 2352.87], [6.0, 2.65, 9437.76],
+    [6.0, 4.67, 4690.48],
+];
+const L2: [[f64; 3]; 20] = [
+    [52919.0, 0.0, 0.0], [8720.0, 1.0721, 6283.0758], [309.0, 0.867, 12566.152],
+    [27.0, 0.05, 3.52], [16.0, 5.19, 26.3], [16.0, 3.68, 155.42],
+    [10.0, 0.76, 18849.23], [9.0, 2.06, 77713.77], [7.0, 0.83, 775.52],
+    [5.0, 4.66, 1577.34], [4.0, 1.03, 7.11], [4.0, 3.44, 5573.14],
+    [3.0, 5.14, 796.3], [3.0, 6.05, 5507.55], [3.0, 1.19, 242.73],
+    [3.0, 6.12, 529.69], [3.0, 0.31, 398.15], [3.0, 2.28, 553.57],
+    [2.0, 4.38, 5223.69], [2.0, 3.75, 0.98],
+];
+const L3: [[f64; 3]; 7] = [
+    [289.0, 5.844, 6283.076], [35.0, 0.0, 0.0], [17.0, 5.49, 12566.15],
+    [3.0, 5.2, 155.42], [1.0, 4.72, 3.52], [1.0, 5.3, 18849.23],
+    [1.0, 5.97, 242.73],
+];
+#[allow(clippy::approx_constant)]  // valores publicados, truncados
+const L4: [[f64; 3]; 3] = [[114.0, 3.142, 0.0], [8.0, 4.13, 6283.08], [1.0, 3.84, 12566.15]];
+#[allow(clippy::approx_constant)]  // valores publicados, truncados
+const L5: [[f64; 3]; 1] = [[1.0, 3.14, 0.0]];
+
+const B0: [[f64; 3]; 5] = [
+    [280.0, 3.199, 84334.662], [102.0, 5.422, 5507.553], [80.0, 3.88, 5223.69],
+    [44.0, 3.7, 2352.87], [32.0, 4.0, 1577.34],
+];
+const B1: [[f64; 3]; 2] = [[9.0, 3.9, 5507.55], [6.0, 1.73, 5223.69]];
+
+const R0: [[f64; 3]; 40] = [
+    [100013989.0, 0.0, 0.0], [1670700.0, 3.0984635, 6283.07585], [13956.0, 3.05525, 12566.1517],
+    [3084.0, 5.1985, 77713.7715], [1628.0, 1.1739, 5753.3849], [1576.0, 2.8469, 7860.4194],
+    [925.0, 5.453, 11506.77], [542.0, 4.564, 3930.21], [472.0, 3.661, 5884.927],
+    [346.0, 0.964, 5507.553], [329.0, 5.9, 5223.694], [307.0, 0.299, 5573.143],
+    [243.0, 4.273, 11790.629], [212.0, 5.847, 1577.344], [186.0, 5.022, 10977.079],
+    [175.0, 3.012, 18849.228], [110.0, 5.055, 5486.778], [98.0, 0.89, 6069.78],
+    [86.0, 5.69, 15720.84], [86.0, 1.27, 161000.69], [65.0, 0.27, 17260.15],
+    [63.0, 0.92, 529.69], [57.0, 2.01, 83996.85], [56.0, 5.24, 71430.7],
+    [49.0, 3.25, 2544.31], [47.0, 2.58, 775.52], [45.0, 5.54, 9437.76],
+    [43.0, 6.01, 6275.96], [39.0, 5.36, 4694.0], [38.0, 2.39, 8827.39],
+    [37.0, 0.83, 19651.05], [37.0, 4.9, 12139.55], [36.0, 1.67, 12036.46],
+    [35.0, 1.84, 2942.46], [33.0, 0.24, 7084.9], [32.0, 0.18, 5088.63],
+    [32.0, 1.78, 398.15], [28.0, 1.21, 6286.6], [28.0, 1.9, 6279.55],
+    [26.0, 4.59, 10447.39],
+];
+#[allow(clippy::approx_constant)]  // valores publicados, truncados
+const R1: [[f64; 3]; 10] = [
+    [103019.0, 1.10749, 6283.07585], [1721.0, 1.0644, 12566.1517], [702.0, 3.142, 0.0],
+    [32.0, 1.02, 18849.23], [31.0, 2.84, 5507.55], [25.0, 1.32, 5223.69],
+    [18.0, 1.42, 1577.34], [10.0, 5.91, 10977.08], [9.0, 1.42, 6275.96],
+    [9.0, 0.27, 5486.78],
+];
+#[allow(clippy::approx_constant)]  // valores publicados, truncados
+const R2: [[f64; 3]; 6] = [
+    [4359.0, 5.7846, 6283.0758], [124.0, 5.579, 12566.152], [12.0, 3.14, 0.0],
+    [9.0, 3.63, 77713.77], [6.0, 1.87, 5573.14], [3.0, 5.47, 18849.23],
+];
+const R3: [[f64; 3]; 2] = [[145.0, 4.273, 6283.076], [7.0, 3.92, 12566.15]];
+const R4: [[f64; 3]; 1] = [[4.0, 2.56, 6283.08]];
+
+// Nutação (tabela A4.3): múltiplos de X0..X4 e coeficientes [a, b, c, d]
+const NUTATION_Y: [[i8; 5]; 63] = [
+    [0, 0, 0, 0, 1], [-2, 0, 0, 2, 2], [0, 0, 0, 2, 2], [0, 0, 0, 0, 2], [0, 1, 0, 0, 0],
+    [0, 0, 1, 0, 0], [-2, 1, 0, 2, 2], [0, 0, 0, 2, 1], [0, 0, 1, 2, 2], [-2, -1, 0, 2, 2],
+    [-2, 0, 1, 0, 0], [-2, 0, 0, 2, 1], [0, 0, -1, 2, 2], [2, 0, 0, 0, 0], [0, 0, 1, 0, 1],
+    [2, 0, -1, 2, 2], [0, 0, -1, 0, 1], [0, 0, 1, 2, 1], [-2, 0, 2, 0, 0], [0, 0, -2, 2, 1],
+    [2, 0, 0, 2, 2], [0, 0, 2, 2, 2], [0, 0, 2, 0, 0], [-2, 0, 1, 2, 2], [0, 0, 0, 2, 0],
+    [-2, 0, 0, 2, 0], [0, 0, -1, 2, 1], [0, 2, 0, 0, 0], [2, 0, -1, 0, 1], [-2, 2, 0, 2, 2],
+    [0, 1, 0, 0, 1], [-2, 0, 1, 0, 1], [0, -1, 0, 0, 1], [0, 0, 2, -2, 0], [2, 0, -1, 2, 1],
+    [2, 0, 1, 2, 2], [0, 1, 0, 2, 2], [-2, 1, 1, 0, 0], [0, -1, 0, 2, 2], [2, 0, 0, 2, 1],
+    [2, 0, 1, 0, 0], [-2, 0, 2, 2, 2], [-2, 0, 1, 2, 1], [2, 0, -2, 0, 1], [2, 0, 0, 0, 1],
+    [0, -1, 1, 0, 0], [-2, -1, 0, 2, 1], [-2, 0, 0, 0, 1], [0, 0, 2, 2, 1], [-2, 0, 2, 0, 1],
+    [-2, 1, 0, 2, 1], [0, 0, 1, -2, 0], [-1, 0, 1, 0, 0], [-2, 1, 0, 0, 0], [1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0], [0, 0, -2, 2, 2], [-1, -1, 1, 0, 0], [0, 1, 1, 0, 0], [0, -1, 1, 2, 2],
+    [2, -1, -1, 2, 2], [0, 0, 3, 2, 2], [2, -1, 0, 2, 2],
+];
+const NUTATION_PE: [[f64; 4]; 63] = [
+    [-171996.0, -174.2, 92025.0, 8.9], [-13187.0, -1.6, 5736.0, -3.1], [-2274.0, -0.2, 977.0, -0.5],
+    [2062.0, 0.2, -895.0, 0.5], [1426.0, -3.4, 54.0, -0.1], [712.0, 0.1, -7.0, 0.0],
+    [-517.0, 1.2, 224.0, -0.6], [-386.0, -0.4, 200.0, 0.0], [-301.0, 0.0, 129.0, -0.1],
+    [217.0, -0.5, -95.0, 0.3], [-158.0, 0.0, 0.0, 0.0], [129.0, 0.1, -70.0, 0.0],
+    [123.0, 0.0, -53.0, 0.0], [63.0, 0.0, 0.0, 0.0], [63.0, 0.1, -33.0, 0.0],
+    [-59.0, 0.0, 26.0, 0.0], [-58.0, -0.1, 32.0, 0.0], [-51.0, 0.0, 27.0, 0.0],
+    [48.0, 0.0, 0.0, 0.0], [46.0, 0.0, -24.0, 0.0], [-38.0, 0.0, 16.0, 0.0],
+    [-31.0, 0.0, 13.0, 0.0], [29.0, 0.0, 0.0, 0.0], [29.0, 0.0, -12.0, 0.0],
+    [26.0, 0.0, 0.0, 0.0], [-22.0, 0.0, 0.0, 0.0], [21.0, 0.0, -10.0, 0.0],
+    [17.0, -0.1, 0.0, 0.0], [16.0, 0.0, -8.0, 0.0], [-16.0, 0.1, 7.0, 0.0],
+    [-15.0, 0.0, 9.0, 0.0], [-13.0, 0.0, 7.0, 0.0], [-12.0, 0.0, 6.0, 0.0],
+    [11.0, 0.0, 0.0, 0.0], [-10.0, 0.0, 5.0, 0.0], [-8.0, 0.0, 3.0, 0.0],
+    [7.0, 0.0, -3.0, 0.0], [-7.0, 0.0, 0.0, 0.0], [-7.0, 0.0, 3.0, 0.0],
+    [-7.0, 0.0, 3.0, 0.0], [6.0, 0.0, 0.0, 0.0], [6.0, 0.0, -3.0, 0.0],
+    [6.0, 0.0, -3.0, 0.0], [-6.0, 0.0, 3.0, 0.0], [-6.0, 0.0, 3.0, 0.0],
+    [5.0, 0.0, 0.0, 0.0], [-5.0, 0.0, 3.0, 0.0], [-5.0, 0.0, 3.0, 0.0],
+    [-5.0, 0.0, 3.0, 0.0], [4.0, 0.0, 0.0, 0.0], [4.0, 0.0, 0.0, 0.0],
+    [4.0, 0.0, 0.0, 0.0], [-4.0, 0.0, 0.0, 0.0], [-4.0, 0.0, 0.0, 0.0],
+    [-4.0, 0.0, 0.0, 0.0], [3.0, 0.0, 0.0, 0.0], [-3.0, 0.0, 0.0, 0.0],
+    [-3.0, 0.0, 0.0, 0.0], [-3.0, 0.0, 0.0, 0.0], [-3.0, 0.0, 0.0, 0.0],
+    [-3.0, 0.0, 0.0, 0.0], [-3.0, 0.0, 0.0, 0.0], [-3.0, 0.0, 0.0, 0.0],
+];
+
+/// UTC instant, proleptic Gregorian calendar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UtcTime {
+    pub year: i32,
+    pub month: u32,     // 1..=12
+    pub day: u32,       // 1..=31
+    pub hour: u32,
+    pub minute: u32,
+    pub second: f64,
+}
+
+impl UtcTime {
+    pub fn new(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: f64) -> Self {
+        assert!((1..=12).contains(&month) && (1..=31).contains(&day), "UtcTime::new: data inválida {}-{}-{}", year, month, day);
+        return UtcTime { year, month, day, hour, minute, second };
+    }
+
+    /// From seconds since 1970-01-01 00:00 UTC (leap seconds ignored, as in Unix time).
+    pub fn from_unix(seconds: f64) -> Self {
+        let days: i64 = (seconds / 86400.0).floor() as i64;
+        let mut rest: f64 = seconds - days as f64 * 86400.0;
+        // dias desde a época -> data civil (algoritmo de H. Hinnant)
+        let z: i64 = days + 719468;
+        let era: i64 = z.div_euclid(146097);
+        let doe: i64 = z - era * 146097;
+        let yoe: i64 = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy: i64 = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp: i64 = (5 * doy + 2) / 153;
+        let day: u32 = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month: u32 = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year: i32 = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        let hour: u32 = (rest / 3600.0) as u32;
+        rest -= hour as f64 * 3600.0;
+        let minute: u32 = (rest / 60.0) as u32;
+        return UtcTime { year, month, day, hour, minute, second: rest - minute as f64 * 60.0 };
+    }
+
+    /// Julian day (UT).
+    pub fn julian_day(&self) -> f64 {
+        let (mut y, mut m): (f64, f64) = (self.year as f64, self.month as f64);
+        if m < 3.0 {
+            y -= 1.0;
+            m += 12.0;
+        }
+        let day: f64 = self.day as f64 + (self.hour as f64 + (self.minute as f64 + self.second / 60.0) / 60.0) / 24.0;
+        let jd: f64 = (365.25 * (y + 4716.0)).floor() + (30.6001 * (m + 1.0)).floor() + day - 1524.5;
+        if jd < 2299160.0 {
+            return jd;  // calendário juliano
+        }
+        let a: f64 = (y / 100.0).floor();
+        return jd + 2.0 - a + (a / 4.0).floor();
+    }
+
+    /// Day of the year, 1 on January 1st.
+    pub fn day_of_year(&self) -> u32 {
+        let jan1 = UtcTime { month: 1, day: 1, hour: 0, minute: 0, second: 0.0, ..*self };
+        let midnight = UtcTime { hour: 0, minute: 0, second: 0.0, ..*self };
+        return (midnight.julian_day() - jan1.julian_day()).round() as u32 + 1;
+    }
+}
+
+/// Observer location and atmosphere (the latter only for refraction).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Site {
+    pub latitude: f64,      // [deg] north positive
+    pub longitude: f64,     // [deg] east positive
+    pub altitude: f64,      // [m]
+    pub pressure: f64,      // [mbar] annual mean local pressure
+    pub temperature: f64,   // [°C] annual mean local temperature
+}
+
+impl Site {
+    /// At sea level, 1013.25 mbar and 12 °C.
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        return Site { latitude, longitude, altitude: 0.0, pressure: 1013.25, temperature: 12.0 };
+    }
+
+    /// builder
+    pub fn with_altitude(mut self, altitude: f64) -> Self { self.altitude = altitude; return self; }
+    pub fn with_atmosphere(mut self, pressure: f64, temperature: f64) -> Self { self.pressure = pressure; self.temperature = temperature; return self; }
+}
+
+/// Algorithm of [`solar_position`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolarAlgorithm {
+    Spa { delta_t: f64 },   // NREL SPA, ±0.0003°; `delta_t` [s] = TT - UT
+    Fast,                   // low-precision almanac (Michalsky), ~0.01° between 1950 and 2050
+}
+
+impl Default for SolarAlgorithm {
+    fn default() -> Self {
+        SolarAlgorithm::Spa { delta_t: DELTA_T_DEFAULT }
+    }
+}
+
+/// Topocentric position of the sun, with atmospheric refraction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolarPosition {
+    pub zenith: f64,            // [deg] apparent
+    pub azimuth: f64,           // [deg] clockwise from north
+    pub elevation: f64,         // [deg] apparent, 90 - zenith
+    pub declination: f64,       // [deg]
+    pub equation_of_time: f64,  // [min] apparent minus mean solar time
+    pub earth_sun_distance: f64,// [AU]
+    pub dni_extra: f64,         // [W/m2] extraterrestrial normal irradiance
+}
+
+/// Sun position seen from `site` at `time`.
+pub fn solar_position(time: &UtcTime, site: &Site, algorithm: SolarAlgorithm) -> SolarPosition {
+    match algorithm {
+        SolarAlgorithm::Spa { delta_t } => return spa(time.julian_day(), site, delta_t),
+        SolarAlgorithm::Fast => return almanac(time.julian_day(), site),
+    }
+}
+
+/// Extraterrestrial normal irradiance [W/m2] on day `day_of_year` (Spencer, 1971).
+pub fn extraterrestrial_irradiance(day_of_year: u32) -> f64 {
+    let b: f64 = 2.0 * std::f64::consts::PI * (day_of_year as f64 - 1.0) / 365.0;
+    let r2: f64 = 1.00011 + 0.034221 * b.cos() + 0.00128 * b.sin() + 0.000719 * (2.0 * b).cos() + 0.000077 * (2.0 * b).sin();
+    return SOLAR_CONSTANT * r2;
+}
+
+fn limit_degrees(deg: f64) -> f64 {
+    return deg.rem_euclid(360.0);
+}
+
+/// Equation of time [min] from the mean longitude `m` and the apparent right ascension `alpha` [deg].
+fn equation_of_time(m: f64, alpha: f64, correction: f64) -> f64 {
+    let e: f64 = 4.0 * (m - 0.0057183 - alpha + correction);
+    return (e + 720.0).rem_euclid(1440.0) - 720.0;
+}
+
+/// Sum of `A * cos(B + C * t)` over the terms of each power of `t`, as a polynomial in `t`.
+fn periodic(series: &[&[[f64; 3]]], t: f64) -> f64 {
+    let mut sum: f64 = 0.0;
+    for (k, terms) in series.iter().enumerate() {
+        let s: f64 = terms.iter().map(|[a, b, c]| a * (b + c * t).cos()).sum();
+        sum += s * t.powi(k as i32);
+    }
+    return sum / 1e8;
+}
+
+/// Topocentric zenith and azimuth from the hour angle `h`, the declination `delta` and the
+/// observer latitude [deg], with refraction corrected for the site atmosphere.
+fn horizon(h: f64, delta: f64, site: &Site) -> (f64, f64, f64) {
+    let (phi, h, delta): (f64, f64, f64) = (site.latitude.to_radians(), h.to_radians(), delta.to_radians());
+    let e0: f64 = (phi.sin() * delta.sin() + phi.cos() * delta.cos() * h.cos()).asin().to_degrees();
+    let de: f64 = if e0 >= -(SUN_RADIUS + ATMOS_REFRACT) {
+        (site.pressure / 1010.0) * (283.0 / (273.0 + site.temperature)) * 1.02 / (60.0 * (e0 + 10.3 / (e0 + 5.11)).to_radians().tan())
+    } else {
+        0.0
+    };
+    let e: f64 = e0 + de;
+    let gamma: f64 = h.sin().atan2(h.cos() * phi.sin() - delta.tan() * phi.cos()).to_degrees();
+    return (90.0 - e, limit_degrees(gamma + 180.0), e);
+}
+
+/// NREL Solar Position Algorithm (Reda & Andreas, 2004).
+fn spa(jd: f64, site: &Site, delta_t: f64) -> SolarPosition {
+    let jde: f64 = jd + delta_t / 86400.0;
+    let jc: f64 = (jd - J2000) / 36525.0;
+    let jce: f64 = (jde - J2000) / 36525.0;
+    let jme: f64 = jce / 10.0;
+
+    // posição heliocêntrica da Terra
+    let l: f64 = limit_degrees(periodic(&[&L0, &L1, &L2, &L3, &L4, &L5], jme).to_degrees());
+    let b: f64 = periodic(&[&B0, &B1], jme).to_degrees();
+    let r: f64 = periodic(&[&R0, &R1, &R2, &R3, &R4], jme);
+    let theta: f64 = limit_degrees(l + 180.0);
+    let beta: f64 = -b;
+
+    // nutação e obliquidade
+    let x: [f64; 5] = [
+        297.85036 + 445267.111480 * jce - 0.0019142 * jce.powi(2) + jce.powi(3) / 189474.0,
+        357.52772 + 35999.050340 * jce - 0.0001603 * jce.powi(2) - jce.powi(3) / 300000.0,
+        134.96298 + 477198.867398 * jce + 0.0086972 * jce.powi(2) + jce.powi(3) / 56250.0,
+        93.27191 + 483202.017538 * jce - 0.0036825 * jce.powi(2) + jce.powi(3) / 327270.0,
+        125.04452 - 1934.136261 * jce + 0.0020708 * jce.powi(2) + jce.powi(3) / 450000.0,
+    ];
+    let mut d_psi: f64 = 0.0;
+    let mut d_eps: f64 = 0.0;
+    for (y, [a, b, c, d]) in NUTATION_Y.iter().zip(NUTATION_PE.iter()) {
+        let arg: f64 = y.iter().zip(x.iter()).map(|(&yi, xi)| yi as f64 * xi).sum::<f64>().to_radians();
+        d_psi += (a + b * jce) * arg.sin();
+        d_eps += (c + d * jce) * arg.cos();
+    }
+    d_psi /= 36000000.0;
+    d_eps /= 36000000.0;
+    let u: f64 = jme / 10.0;
+    let eps0: f64 = [84381.448, -4680.93, -1.55, 1999.25, -51.38, -249.67, -39.05, 7.12, 27.87, 5.79, 2.45]
+        .iter().rev().fold(0.0, |acc, c| acc * u + c);
+    let eps: f64 = eps0 / 3600.0 + d_eps;
+
+    // longitude aparente, tempo sideral e coordenadas geocêntricas
+    let lambda: f64 = theta + d_psi - 20.4898 / (3600.0 * r);
+    let nu0: f64 = limit_degrees(280.46061837 + 360.98564736629 * (jd - J2000) + 0.000387933 * jc.powi(2) - jc.powi(3) / 38710000.0);
+    let nu: f64 = nu0 + d_psi * eps.to_radians().cos();
+    let (lr, er, br): (f64, f64, f64) = (lambda.to_radians(), eps.to_radians(), beta.to_radians());
+    let alpha: f64 = limit_degrees((lr.sin() * er.cos() - br.tan() * er.sin()).atan2(lr.cos()).to_degrees());
+    let delta: f64 = (br.sin() * er.cos() + br.cos() * er.sin() * lr.sin()).asin().to_degrees();
+    let h: f64 = limit_degrees(nu + site.longitude - alpha);
+
+    // paralaxe: coordenadas topocêntricas
+    let xi: f64 = (8.794 / (3600.0 * r)).to_radians();
+    let phi: f64 = site.latitude.to_radians();
+    let u_: f64 = (0.99664719 * phi.tan()).atan();
+    let xp: f64 = u_.cos() + site.altitude / EARTH_RADIUS * phi.cos();
+    let yp: f64 = 0.99664719 * u_.sin() + site.altitude / EARTH_RADIUS * phi.sin();
+    let (hr, dr): (f64, f64) = (h.to_radians(), delta.to_radians());
+    let d_alpha: f64 = (-xp * xi.sin() * hr.sin()).atan2(dr.cos() - xp * xi.sin() * hr.cos());
+    let delta_p: f64 = ((dr.sin() - yp * xi.sin()) * d_alpha.cos()).atan2(dr.cos() - xp * xi.sin() * hr.cos()).to_degrees();
+    let h_p: f64 = h - d_alpha.to_degrees();
+    let (zenith, azimuth, elevation) = horizon(h_p, delta_p, site);
+
+    let m: f64 = limit_degrees(280.4664567 + 360007.6982779 * jme + 0.03032028 * jme.powi(2) + jme.powi(3) / 49931.0
+        - jme.powi(4) / 15300.0 - jme.powi(5) / 2000000.0);
+    let eot: f64 = equation_of_time(m, alpha, d_psi * eps.to_radians().cos());
+    return SolarPosition { zenith, azimuth, elevation, declination: delta_p, equation_of_time: eot, earth_sun_distance: r, dni_extra: SOLAR_CONSTANT / (r * r) };
+}
+
+/// Low-precision almanac position (Michalsky, 1988), without parallax.
+fn almanac(jd: f64, site: &Site) -> SolarPosition {
+    let n: f64 = jd - J2000;
+    let l: f64 = limit_degrees(280.460 + 0.9856474 * n);
+    let g: f64 = limit_degrees(357.528 + 0.9856003 * n).to_radians();
+    let lambda: f64 = (l + 1.915 * g.sin() + 0.020 * (2.0 * g).sin()).to_radians();
+    let eps: f64 = (23.439 - 0.0000004 * n).to_radians();
+    let alpha: f64 = limit_degrees((eps.cos() * lambda.sin()).atan2(lambda.cos()).to_degrees());
+    let delta: f64 = (eps.sin() * lambda.sin()).asin().to_degrees();
+    let gmst: f64 = limit_degrees(280.46061837 + 360.98564736629 * n);
+    let h: f64 = limit_degrees(gmst + site.longitude - alpha);
+    let r: f64 = 1.00014 - 0.01671 * g.cos() - 0.00014 * (2.0 * g).cos();
+    let (zenith, azimuth, elevation) = horizon(h, delta, site);
+    let eot: f64 = equation_of_time(l, alpha, 0.0057183);
+    return SolarPosition { zenith, azimuth, elevation, declination: delta, equation_of_time: eot, earth_sun_distance: r, dni_extra: SOLAR_CONSTANT / (r * r) };
+}
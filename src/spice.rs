@@ -0,0 +1,133 @@
+use std::fmt::{self, Write};
+use crate::pvcell::{BypassDiode, PvCell, PvCellState, C_TO_K, Q_K};
+use crate::series::Series;
+use crate::parallel::Parallel;
+use crate::module::{Module, SubstringState};
+use crate::element::Element;
+
+/// Device temperature [°C] of the exported diodes: `TNOM` and the instance `TEMP` are both set to it,
+/// so the simulator does not rescale the saturation currents computed for the state.
+const SPICE_TEMP_C: f64 = 25.0;
+
+/// Saturation current [A] and ideality factor of the near-ideal diode of the linear bypass.
+const IDEAL_IS: f64 = 1e-12;
+const IDEAL_N: f64 = 0.01;
+
+/// Export to a SPICE subcircuit `.SUBCKT name pos neg` (ngspice syntax), with the element frozen
+/// at a given state: photocurrent sources, diodes, resistances and bypass diodes.
+///
+/// The subcircuits of the children are written first, named `name_0`, `name_1`, ...
+pub trait SpiceExport: Element {
+    fn write_spice<W: Write>(&self, state: &Self::State, name: &str, out: &mut W) -> fmt::Result;
+
+    fn to_spice(&self, state: &Self::State, name: &str) -> String {
+        let mut out = String::new();
+        self.write_spice(state, name, &mut out).unwrap();  // escrita em String não falha
+        return out;
+    }
+}
+
+/// Diode model `.MODEL name D (...)`; `scale = (ns, np)` stacks `ns` diodes in series and `np` in parallel.
+fn write_diode_model<W: Write>(out: &mut W, name: &str, i_s: f64, n: f64, r_s: f64, temp_c: f64, scale: (f64, f64)) -> fmt::Result {
+    let (scale_v, scale_i) = scale;
+    write!(out, ".MODEL {} D (IS={:e} N={:e}", name, i_s * scale_i, n * scale_v)?;
+    if r_s > 0.0 {
+        write!(out, " RS={:e}", r_s * scale_v / scale_i)?;
+    }
+    writeln!(out, " TNOM={})", temp_c)
+}
+
+fn write_bypass_diode<W: Write>(out: &mut W, instance: &str, cathode: &str, anode: &str, model: &str, d: &BypassDiode, scale: (f64, f64)) -> fmt::Result {
+    let temp_c: f64 = d.temp - C_TO_K;
+    writeln!(out, "{} {} {} {} TEMP={}", instance, anode, cathode, model, temp_c)?;
+    write_diode_model(out, model, d.i_s, d.n, d.r_s, temp_c, scale)
+}
+
+/// Single-diode (or two-diode) equivalent of the `ns` x `np` cells with the voltages scaled by `ns`
+/// and the currents by `np`. The linear bypass (`v_bypass`/`r_bypass`) becomes a near-ideal diode in
+/// series with a voltage source and `r_bypass`; the Bishop breakdown, a behavioural current source.
+impl SpiceExport for PvCell {
+    fn write_spice<W: Write>(&self, state: &PvCellState, name: &str, out: &mut W) -> fmt::Result {
+        let ns: f64 = self.ns as f64;
+        let np: f64 = self.np as f64;
+        let vt: f64 = (SPICE_TEMP_C + C_TO_K) / Q_K;   // [V] k*T/q
+        writeln!(out, ".SUBCKT {} pos neg", name)?;
+        writeln!(out, "* PvCell ns={} np={}", self.ns, self.np)?;
+        writeln!(out, "IL neg j DC {:e}", np * state.il)?;
+        writeln!(out, "D1 j neg {}_d1 TEMP={}", name, SPICE_TEMP_C)?;
+        if state.i02 > 0.0 {
+            writeln!(out, "D2 j neg {}_d2 TEMP={}", name, SPICE_TEMP_C)?;
+        }
+        let gsh: f64 = state.gsh * np / ns;
+        match &self.breakdown {
+            Some(br) => writeln!(out, "BSH j neg I={:e}*V(j,neg)*(1+{:e}*pow(1-V(j,neg)/({:e}),{:e}))", gsh, br.a, br.v_br * ns, -br.m)?,
+            None if gsh > 0.0 => writeln!(out, "RSH j neg {:e}", 1.0 / gsh)?,
+            None => {},
+        }
+        if self.r_s > 0.0 {
+            writeln!(out, "RS j pos {:e}", self.r_s * ns / np)?;
+        } else {
+            writeln!(out, "VRS j pos DC 0")?;
+        }
+        match &state.bypass {
+            Some(d) => write_bypass_diode(out, "DBP", "pos", "neg", &format!("{}_dbp", name), d, (ns, np))?,
+            None if self.v_bypass.is_finite() => {
+                writeln!(out, "DBP neg b {}_dbp TEMP={}", name, SPICE_TEMP_C)?;
+                writeln!(out, "VBP b c DC {:e}", -self.v_bypass * ns)?;
+                writeln!(out, "RBP c pos {:e}", self.r_bypass * ns / np)?;
+                write_diode_model(out, &format!("{}_dbp", name), IDEAL_IS, IDEAL_N, 0.0, SPICE_TEMP_C, (ns, np))?;
+            }
+            None => {},
+        }
+        write_diode_model(out, &format!("{}_d1", name), state.i0, 1.0 / (state.ra * vt), 0.0, SPICE_TEMP_C, (ns, np))?;
+        if state.i02 > 0.0 {
+            write_diode_model(out, &format!("{}_d2", name), state.i02, 1.0 / (state.ra2 * vt), 0.0, SPICE_TEMP_C, (ns, np))?;
+        }
+        writeln!(out, ".ENDS {}", name)
+    }
+}
+
+impl<E: SpiceExport> SpiceExport for Series<E> {
+    fn write_spice<W: Write>(&self, states: &Vec<E::State>, name: &str, out: &mut W) -> fmt::Result {
+        for (k, e) in self.elements.iter().enumerate() {
+            e.write_spice(&states[k], &format!("{}_{}", name, k), out)?;
+        }
+        writeln!(out, ".SUBCKT {} pos neg", name)?;
+        for k in 0..self.len() {
+            let lo: String = if k == 0 { "neg".to_string() } else { format!("n{}", k) };
+            let hi: String = if k + 1 == self.len() { "pos".to_string() } else { format!("n{}", k + 1) };
+            writeln!(out, "X{} {} {} {}_{}", k, hi, lo, name, k)?;
+        }
+        writeln!(out, ".ENDS {}", name)
+    }
+}
+
+impl<E: SpiceExport> SpiceExport for Parallel<E> {
+    fn write_spice<W: Write>(&self, states: &Vec<E::State>, name: &str, out: &mut W) -> fmt::Result {
+        for (k, e) in self.elements.iter().enumerate() {
+            e.write_spice(&states[k], &format!("{}_{}", name, k), out)?;
+        }
+        writeln!(out, ".SUBCKT {} pos neg", name)?;
+        for k in 0..self.len() {
+            writeln!(out, "X{} pos neg {}_{}", k, name, k)?;
+        }
+        writeln!(out, ".ENDS {}", name)
+    }
+}
+
+/// Substrings `name_0`, `name_1`, ... in series, each with its bypass diode (at the temperature of the state) in the module subcircuit.
+impl SpiceExport for Module {
+    fn write_spice<W: Write>(&self, states: &Vec<SubstringState>, name: &str, out: &mut W) -> fmt::Result {
+        for (k, sub) in self.substrings.iter().enumerate() {
+            sub.cells.write_spice(&states[k].cells, &format!("{}_{}", name, k), out)?;
+        }
+        writeln!(out, ".SUBCKT {} pos neg", name)?;
+        for (k, state) in states.iter().enumerate() {
+            let lo: String = if k == 0 { "neg".to_string() } else { format!("n{}", k) };
+            let hi: String = if k + 1 == self.len() { "pos".to_string() } else { format!("n{}", k + 1) };
+            writeln!(out, "X{} {} {} {}_{}", k, hi, lo, name, k)?;
+            write_bypass_diode(out, &format!("DBP{}", k), &hi, &lo, &format!("{}_dbp{}", name, k), &state.bypass, (1.0, 1.0))?;
+        }
+        writeln!(out, ".ENDS {}", name)
+    }
+}
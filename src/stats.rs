@@ -0,0 +1,62 @@
+/// Counters of one solver level. `max_residual` is the largest finite final residual
+/// seen, in the units of the solver tolerance; `non_finite` counts the solves ending
+/// on a NaN or infinite residual (failed bracket, singular Jacobian, ...).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SolverCounters {
+    pub calls: usize,
+    pub iterations: usize,
+    pub non_converged: usize,
+    pub max_residual: f64,
+    pub non_finite: usize,
+}
+
+impl SolverCounters {
+    pub(crate) fn record(&mut self, iterations: usize, residual: f64, converged: bool) {
+        self.calls += 1;
+        self.iterations += iterations;
+        if !converged {
+            self.non_converged += 1;
+        }
+        if !residual.is_finite() {
+            self.non_finite += 1;
+        } else if residual > self.max_residual {
+            self.max_residual = residual;
+        }
+    }
+
+    pub fn merge(&mut self, other: &SolverCounters) {
+        self.calls += other.calls;
+        self.iterations += other.iterations;
+        self.non_converged += other.non_converged;
+        self.non_finite += other.non_finite;
+        if other.max_residual > self.max_residual {
+            self.max_residual = other.max_residual;
+        }
+    }
+}
+
+/// Solver statistics owned by the caller and accumulated by the `*_with_stats` solves.
+///
+/// Each thread keeps its own `SolverStats`; combine them afterwards with [`SolverStats::merge`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SolverStats {
+    pub cell: SolverCounters,     // PvCell::solve_i / PvCell::v_from_i
+    pub series: SolverCounters,   // Series::i_from_v
+    pub parallel: SolverCounters, // Parallel solves
+    pub module: SolverCounters,   // Module and substring solves
+    pub netlist: SolverCounters,  // Netlist Newton solves
+}
+
+impl SolverStats {
+    pub fn new() -> Self {
+        SolverStats::default()
+    }
+
+    pub fn merge(&mut self, other: &SolverStats) {
+        self.cell.merge(&other.cell);
+        self.series.merge(&other.series);
+        self.parallel.merge(&other.parallel);
+        self.module.merge(&other.module);
+        self.netlist.merge(&other.netlist);
+    }
+}
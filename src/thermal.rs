@@ -0,0 +1,150 @@
+use crate::element::Element;
+use crate::irradiance::PoaIrradiance;
+
+/// Ambient conditions of a timestep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ambient {
+    pub temp_air: f64,      // [°C]
+    pub wind_speed: f64,    // [m/s] at the height the model coefficients were fitted for
+}
+
+impl Ambient {
+    pub fn new(temp_air: f64, wind_speed: f64) -> Self {
+        return Ambient { temp_air, wind_speed };
+    }
+}
+
+/// Steady-state cell temperature model, from POA irradiance [W/m2] and [`Ambient`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThermalModel {
+    /// Duffie & Beckman: `Ta + G/800 (NOCT - 20) (1 - eta/0.9) 9.5/(5.7 + 3.8 ws)`.
+    Noct { noct: f64, eta: f64 },
+    /// Sandia (King, 2004): back surface `Ta + G exp(a + b ws)`, cell `+ G/1000 dt`.
+    Sandia { a: f64, b: f64, delta_t: f64 },
+    /// Faiman (2008): `Ta + G/(u0 + u1 ws)`.
+    Faiman { u0: f64, u1: f64 },
+    /// PVsyst: `Ta + G alpha (1 - eta)/(u_c + u_v ws)`.
+    Pvsyst { u_c: f64, u_v: f64, alpha: f64, eta: f64 },
+    /// Ross (1980): `Ta + k G`, no wind.
+    Ross { k: f64 },
+}
+
+impl Default for ThermalModel {
+    fn default() -> Self {
+        ThermalModel::faiman()
+    }
+}
+
+impl ThermalModel {
+    /// NOCT [°C] from the datasheet, module efficiency 0.15.
+    pub fn noct(noct: f64) -> Self {
+        return ThermalModel::Noct { noct, eta: 0.15 };
+    }
+
+    /// Glass/cell/polymer sheet, open rack.
+    pub fn sandia_open_rack() -> Self {
+        return ThermalModel::Sandia { a: -3.56, b: -0.075, delta_t: 3.0 };
+    }
+
+    /// Glass/cell/glass, close roof mount.
+    pub fn sandia_close_roof() -> Self {
+        return ThermalModel::Sandia { a: -2.98, b: -0.0471, delta_t: 1.0 };
+    }
+
+    pub fn faiman() -> Self {
+        return ThermalModel::Faiman { u0: 25.0, u1: 6.84 };
+    }
+
+    /// Free-standing racks.
+    pub fn pvsyst_freestanding() -> Self {
+        return ThermalModel::Pvsyst { u_c: 29.0, u_v: 0.0, alpha: 0.9, eta: 0.1 };
+    }
+
+    /// Fully insulated back (building integrated).
+    pub fn pvsyst_insulated() -> Self {
+        return ThermalModel::Pvsyst { u_c: 15.0, u_v: 0.0, alpha: 0.9, eta: 0.1 };
+    }
+
+    /// Well-cooled free-standing array.
+    pub fn ross() -> Self {
+        return ThermalModel::Ross { k: 0.02 };
+    }
+
+    /// Cell temperature [°C] at POA irradiance `poa_global` [W/m2].
+    pub fn cell_temperature(&self, poa_global: f64, ambient: &Ambient) -> f64 {
+        let g: f64 = poa_global.max(0.0);
+        let ws: f64 = ambient.wind_speed.max(0.0);
+        let rise: f64 = match *self {
+            ThermalModel::Noct { noct, eta } => g / 800.0 * (noct - 20.0) * (1.0 - eta / 0.9) * 9.5 / (5.7 + 3.8 * ws),
+            ThermalModel::Sandia { a, b, delta_t } => g * (a + b * ws).exp() + g / 1000.0 * delta_t,
+            ThermalModel::Faiman { u0, u1 } => g / (u0 + u1 * ws),
+            ThermalModel::Pvsyst { u_c, u_v, alpha, eta } => g * alpha * (1.0 - eta) / (u_c + u_v * ws),
+            ThermalModel::Ross { k } => k * g,
+        };
+        return ambient.temp_air + rise;
+    }
+
+    /// Module back-surface temperature [°C] at POA irradiance `poa_global` [W/m2]. Only the Sandia
+    /// model tells it apart from the cell temperature; the others return the cell temperature.
+    pub fn module_temperature(&self, poa_global: f64, ambient: &Ambient) -> f64 {
+        match *self {
+            ThermalModel::Sandia { a, b, .. } => {
+                let g: f64 = poa_global.max(0.0);
+                let ws: f64 = ambient.wind_speed.max(0.0);
+                return ambient.temp_air + g * (a + b * ws).exp();
+            }
+            _ => return self.cell_temperature(poa_global, ambient),
+        }
+    }
+
+    /// Cell temperatures [°C], one per irradiance of `poa_global`.
+    pub fn cell_temperatures(&self, poa_global: &[f64], ambient: &Ambient) -> Vec<f64> {
+        return poa_global.iter().map(|&g| self.cell_temperature(g, ambient)).collect();
+    }
+}
+
+/// First-order module thermal model: the cell temperature relaxes towards the steady state of `steady`
+/// with time constant `tau`, `dT/dt = (T_ss - T)/tau`, integrated exactly over each step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransientThermal {
+    pub steady: ThermalModel,
+    pub tau: f64,               // [s]
+    pub temperature: Option<f64>,   // [°C] current cell temperature; `None` starts at the steady state
+}
+
+impl TransientThermal {
+    pub fn new(steady: ThermalModel, tau: f64) -> Self {
+        assert!(tau > 0.0, "TransientThermal::new: constante de tempo deve ser positiva");
+        return TransientThermal { steady, tau, temperature: None };
+    }
+
+    /// Lumped heat capacity: `tau = heat_capacity/u`, with the areal heat capacity [J/(m2 K)] and the
+    /// overall heat loss coefficient `u` [W/(m2 K)] of the module (about 11000 and 30 for glass/polymer).
+    pub fn from_heat_capacity(steady: ThermalModel, heat_capacity: f64, u: f64) -> Self {
+        return TransientThermal::new(steady, heat_capacity / u);
+    }
+
+    /// Advances `dt` [s] with the conditions held over the step and returns the cell temperature [°C].
+    pub fn step(&mut self, poa_global: f64, ambient: &Ambient, dt: f64) -> f64 {
+        let t_ss: f64 = self.steady.cell_temperature(poa_global, ambient);
+        let t: f64 = match self.temperature {
+            Some(t0) => t_ss + (t0 - t_ss) * (-dt.max(0.0) / self.tau).exp(),
+            None => t_ss,
+        };
+        self.temperature = Some(t);
+        return t;
+    }
+
+    /// Cell temperatures [°C] of a time series sampled every `dt` [s], continuing from the current temperature.
+    pub fn run(&mut self, poa_global: &[f64], ambient: &[Ambient], dt: f64) -> Vec<f64> {
+        assert_eq!(poa_global.len(), ambient.len(), "TransientThermal::run: uma condição ambiente por irradiância");
+        return poa_global.iter().zip(ambient.iter()).map(|(&g, a)| self.step(g, a, dt)).collect();
+    }
+
+    /// One state of `element` per timestep, at the cell temperature of [`TransientThermal::run`].
+    pub fn states<E: Element>(&mut self, element: &E, poa: &[PoaIrradiance], ambient: &[Ambient], dt: f64) -> Vec<E::State> {
+        let global: Vec<f64> = poa.iter().map(|p| p.global()).collect();
+        let temps: Vec<f64> = self.run(&global, ambient, dt);
+        return poa.iter().zip(temps).map(|(p, t)| element.compute_state_poa(p, t)).collect();
+    }
+}